@@ -10,10 +10,11 @@ use std::path::Path;
 use std::process::Command;
 
 /// Run the `wasm-bindgen` CLI to generate bindings for the current crate's
-/// `.wasm`.
+/// `.wasm`, which cargo compiled to `wasm_path`.
 pub fn wasm_bindgen_build(
     data: &CrateData,
     install_status: &install::Status,
+    wasm_path: &Path,
     out_dir: &Path,
     out_name: &Option<String>,
     disable_dts: bool,
@@ -21,83 +22,84 @@ pub fn wasm_bindgen_build(
     reference_types: bool,
     target: Target,
     profile: BuildProfile,
-    extra_options: &Vec<String>,
 ) -> Result<()> {
-    let profile_name = match profile.clone() {
-        BuildProfile::Release | BuildProfile::Profiling => "release",
-        BuildProfile::Dev => "debug",
-        BuildProfile::Custom(profile_name) => &profile_name.clone(),
-    };
-
     let out_dir = out_dir.to_str().unwrap();
 
-    let target_directory = {
-        let mut has_target_dir_iter = extra_options.iter();
-        has_target_dir_iter
-            .find(|&it| it == "--target-dir")
-            .and_then(|_| has_target_dir_iter.next())
-            .map(Path::new)
-            .unwrap_or(data.target_directory())
-    };
+    let bindgen_path = install::get_tool_path(install_status, Tool::WasmBindgen)?;
+
+    let mut cmd = Command::new(&bindgen_path);
+    cmd.arg(wasm_path).arg("--out-dir").arg(out_dir);
+
+    let target_arg = build_target_arg(target, &bindgen_path)?;
+    if supports_dash_dash_target(&bindgen_path)? {
+        cmd.arg("--target").arg(target_arg);
+    } else {
+        cmd.arg(target_arg);
+    }
+
+    cmd.args(wasm_bindgen_flags(
+        data,
+        out_name,
+        disable_dts,
+        weak_refs,
+        reference_types,
+        profile,
+    ));
+
+    child::run(cmd, "wasm-bindgen").context("Running the wasm-bindgen CLI")?;
+    Ok(())
+}
 
-    let wasm_path = target_directory
-        .join("wasm32-unknown-unknown")
-        .join(profile_name)
-        .join(data.crate_name())
-        .with_extension("wasm");
+/// The flags passed to `wasm-bindgen`, other than the input, output directory
+/// and target.
+pub fn wasm_bindgen_flags(
+    data: &CrateData,
+    out_name: &Option<String>,
+    disable_dts: bool,
+    weak_refs: bool,
+    reference_types: bool,
+    profile: BuildProfile,
+) -> Vec<String> {
+    let mut flags = Vec::new();
 
     let dts_arg = if disable_dts {
         "--no-typescript"
     } else {
         "--typescript"
     };
-    let bindgen_path = install::get_tool_path(install_status, Tool::WasmBindgen)?
-        .binary(&Tool::WasmBindgen.to_string())?;
-
-    let mut cmd = Command::new(&bindgen_path);
-    cmd.arg(&wasm_path)
-        .arg("--out-dir")
-        .arg(out_dir)
-        .arg(dts_arg);
+    flags.push(dts_arg.to_string());
 
     if weak_refs {
-        cmd.arg("--weak-refs");
+        flags.push("--weak-refs".to_string());
     }
 
     if reference_types {
-        cmd.arg("--reference-types");
-    }
-
-    let target_arg = build_target_arg(target, &bindgen_path)?;
-    if supports_dash_dash_target(&bindgen_path)? {
-        cmd.arg("--target").arg(target_arg);
-    } else {
-        cmd.arg(target_arg);
+        flags.push("--reference-types".to_string());
     }
 
     if let Some(value) = out_name {
-        cmd.arg("--out-name").arg(value);
+        flags.push("--out-name".to_string());
+        flags.push(value.clone());
     }
 
     let profile = data.configured_profile(profile);
     if profile.wasm_bindgen_debug_js_glue() {
-        cmd.arg("--debug");
+        flags.push("--debug".to_string());
     }
     if !profile.wasm_bindgen_demangle_name_section() {
-        cmd.arg("--no-demangle");
+        flags.push("--no-demangle".to_string());
     }
     if profile.wasm_bindgen_dwarf_debug_info() {
-        cmd.arg("--keep-debug");
+        flags.push("--keep-debug".to_string());
     }
     if profile.wasm_bindgen_omit_default_module_path() {
-        cmd.arg("--omit-default-module-path");
+        flags.push("--omit-default-module-path".to_string());
     }
     if profile.wasm_bindgen_split_linked_modules() {
-        cmd.arg("--split-linked-modules");
+        flags.push("--split-linked-modules".to_string());
     }
 
-    child::run(cmd, "wasm-bindgen").context("Running the wasm-bindgen CLI")?;
-    Ok(())
+    flags
 }
 
 /// Check if the `wasm-bindgen` dependency is locally satisfied for the web target
@@ -6,10 +6,12 @@ use crate::emoji;
 use crate::manifest::Crate;
 use crate::PBAR;
 use anyhow::{anyhow, bail, Context, Result};
-use std::path::Path;
+use cargo_metadata::{Artifact, Message, PackageId};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str;
 
+pub mod threads;
 pub mod wasm_target;
 
 /// Used when comparing the currently installed
@@ -22,6 +24,21 @@ pub struct WasmPackVersion {
     pub latest: String,
 }
 
+impl WasmPackVersion {
+    /// Whether the latest version is newer than the installed one. Versions
+    /// that aren't valid semver, such as an empty one when the lookup failed,
+    /// are never considered newer.
+    pub fn is_outdated(&self) -> bool {
+        match (
+            semver::Version::parse(&self.local),
+            semver::Version::parse(&self.latest),
+        ) {
+            (Ok(local), Ok(latest)) => latest > local,
+            _ => false,
+        }
+    }
+}
+
 /// Ensure that `rustc` is present and that it is >= 1.30.0
 pub fn check_rustc_version() -> Result<String> {
     let local_minor_version = rustc_minor_version();
@@ -67,21 +84,53 @@ pub fn check_wasm_pack_versions() -> Result<WasmPackVersion> {
     }
 }
 
+/// Look up the latest wasm-pack version right away, rather than using the one
+/// that was looked up within the `update-check-interval`.
+pub fn fetch_wasm_pack_versions() -> Result<WasmPackVersion> {
+    match wasm_pack_local_version() {
+        Some(local) => Ok(WasmPackVersion {
+            local,
+            latest: Crate::fetch_wasm_pack_latest_version()?,
+        }),
+        None => bail!("We can't figure out what your wasm-pack version is, make sure the installation path is correct."),
+    }
+}
+
 fn wasm_pack_local_version() -> Option<String> {
     let output = env!("CARGO_PKG_VERSION");
     Some(output.to_string())
 }
 
-/// Run `cargo build` targetting `wasm32-unknown-unknown`.
+/// The `.wasm` file that cargo compiled for a package's `cdylib` target.
+#[derive(Debug, Clone)]
+pub struct WasmArtifact {
+    /// The id of the package, as in `cargo metadata`.
+    pub package_id: PackageId,
+    /// The path of the `.wasm` file.
+    pub path: PathBuf,
+}
+
+/// Run `cargo build` targetting `rust_target`, usually `wasm32-unknown-unknown`,
+/// and return the `.wasm` files it compiled, as reported by cargo.
+///
+/// With `threads`, the crate is built on a nightly toolchain with atomics and
+/// shared memory enabled, rebuilding the standard library to match.
 pub fn cargo_build_wasm(
     path: &Path,
     profile: BuildProfile,
+    rust_target: &str,
+    threads: bool,
     extra_options: &[String],
-) -> Result<()> {
+) -> Result<Vec<WasmArtifact>> {
     let msg = format!("{}Compiling to Wasm...", emoji::CYCLONE);
     PBAR.info(&msg);
 
     let mut cmd = Command::new("cargo");
+    if threads {
+        if let Some(toolchain) = threads::toolchain(path) {
+            cmd.arg(format!("+{}", toolchain));
+        }
+    }
     cmd.current_dir(path).arg("build").arg("--lib");
 
     if PBAR.quiet() {
@@ -109,7 +158,14 @@ pub fn cargo_build_wasm(
         }
     }
 
-    cmd.arg("--target").arg("wasm32-unknown-unknown");
+    cmd.arg("--target").arg(rust_target);
+    // Diagnostics are still rendered to stderr, while stdout only receives
+    // the JSON messages that tell where the artifacts were written.
+    cmd.arg("--message-format=json-render-diagnostics");
+
+    if threads {
+        threads::configure_cargo_build(&mut cmd, path, rust_target, extra_options);
+    }
 
     // The `cargo` command is executed inside the directory at `path`, so relative paths set via extra options won't work.
     // To remedy the situation, all detected paths are converted to absolute paths.
@@ -132,11 +188,31 @@ pub fn cargo_build_wasm(
         .collect::<Result<Vec<_>>>()?;
     cmd.args(extra_options_with_absolute_paths);
 
-    child::run(cmd, "cargo build").context("Compiling your crate to WebAssembly failed")?;
-    Ok(())
+    let mut artifacts = Vec::new();
+    child::run_with_stdout_lines(cmd, "cargo build", |line| {
+        if let Ok(Message::CompilerArtifact(artifact)) = serde_json::from_str(line) {
+            artifacts.extend(wasm_artifact(artifact));
+        }
+    })
+    .context("Compiling your crate to WebAssembly failed")?;
+    Ok(artifacts)
+}
+
+fn wasm_artifact(artifact: Artifact) -> Option<WasmArtifact> {
+    if !artifact.target.crate_types.iter().any(|ty| ty == "cdylib") {
+        return None;
+    }
+    let path = artifact
+        .filenames
+        .into_iter()
+        .find(|path| path.extension() == Some("wasm"))?;
+    Some(WasmArtifact {
+        package_id: artifact.package_id,
+        path: path.into_std_path_buf(),
+    })
 }
 
-/// Runs `cargo build --tests` targeting `wasm32-unknown-unknown`.
+/// Runs `cargo build --tests` targeting `rust_target`, usually `wasm32-unknown-unknown`.
 ///
 /// This generates the `Cargo.lock` file that we use in order to know which version of
 /// wasm-bindgen-cli to use when running tests.
@@ -148,8 +224,14 @@ pub fn cargo_build_wasm(
 ///
 /// * `path`: Path to the crate directory to build tests.
 /// * `debug`: Whether to build tests in `debug` mode.
+/// * `rust_target`: The target triple, or path to a custom target JSON file, to build for.
 /// * `extra_options`: Additional parameters to pass to `cargo` when building tests.
-pub fn cargo_build_wasm_tests(path: &Path, debug: bool, extra_options: &[String]) -> Result<()> {
+pub fn cargo_build_wasm_tests(
+    path: &Path,
+    debug: bool,
+    rust_target: &str,
+    extra_options: &[String],
+) -> Result<()> {
     let mut cmd = Command::new("cargo");
 
     cmd.current_dir(path).arg("build").arg("--tests");
@@ -162,7 +244,7 @@ pub fn cargo_build_wasm_tests(path: &Path, debug: bool, extra_options: &[String]
         cmd.arg("--release");
     }
 
-    cmd.arg("--target").arg("wasm32-unknown-unknown");
+    cmd.arg("--target").arg(rust_target);
 
     cmd.args(extra_options);
 
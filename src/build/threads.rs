@@ -0,0 +1,207 @@
+//! Building multithreaded wasm, with atomics and shared memory.
+//!
+//! The standard library shipped for the wasm targets is compiled without
+//! atomics, so threaded builds need a nightly toolchain that rebuilds it from
+//! source with `-Z build-std`.
+
+use crate::child;
+use crate::emoji;
+use crate::PBAR;
+use anyhow::{anyhow, bail, Context, Result};
+use log::info;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The toolchain used for threaded builds when the active one isn't nightly.
+const NIGHTLY: &str = "nightly";
+
+/// The flags that enable atomics and shared memory.
+const RUSTFLAGS: &[&str] = &[
+    "-C",
+    "target-feature=+atomics,+bulk-memory,+mutable-globals",
+    "-C",
+    "link-arg=--shared-memory",
+    "-C",
+    "link-arg=--import-memory",
+    "-C",
+    "link-arg=--max-memory=1073741824",
+    "-C",
+    "link-arg=--export=__wasm_init_tls",
+    "-C",
+    "link-arg=--export=__tls_size",
+    "-C",
+    "link-arg=--export=__tls_align",
+    "-C",
+    "link-arg=--export=__tls_base",
+];
+
+/// The toolchain to run cargo and rustc with for a threaded build of the
+/// crate at `path`, or `None` if the active toolchain is already nightly.
+pub fn toolchain(path: &Path) -> Option<&'static str> {
+    let output = Command::new("rustc")
+        .current_dir(path)
+        .arg("--version")
+        .output()
+        .ok()?;
+    let version = String::from_utf8_lossy(&output.stdout);
+    if version.contains("nightly") || version.contains("-dev") {
+        None
+    } else {
+        Some(NIGHTLY)
+    }
+}
+
+/// Configure a `cargo build` command of the crate at `path` for a threaded
+/// build for `rust_target`: enable atomics and shared memory, and rebuild the
+/// standard library with them unless `extra_options` already asks for
+/// `-Z build-std`.
+///
+/// The flags are added to the ones cargo would use anyway. When `RUSTFLAGS`
+/// or `CARGO_ENCODED_RUSTFLAGS` is set, cargo ignores the configured flags,
+/// so they are appended to it. Otherwise they go to the configured
+/// `target.<triple>.rustflags`, which cargo merges with the ones in
+/// `.cargo/config.toml`.
+pub fn configure_cargo_build(
+    cmd: &mut Command,
+    path: &Path,
+    rust_target: &str,
+    extra_options: &[String],
+) {
+    if let Ok(encoded) = env::var("CARGO_ENCODED_RUSTFLAGS") {
+        let mut rustflags: Vec<&str> = encoded.split('\x1f').filter(|f| !f.is_empty()).collect();
+        rustflags.extend(RUSTFLAGS);
+        info!("Building with CARGO_ENCODED_RUSTFLAGS={:?}", rustflags);
+        cmd.env("CARGO_ENCODED_RUSTFLAGS", rustflags.join("\x1f"));
+    } else if let Ok(rustflags) = env::var("RUSTFLAGS") {
+        let rustflags = rustflags
+            .split_whitespace()
+            .chain(RUSTFLAGS.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ");
+        info!("Building with RUSTFLAGS={:?}", rustflags);
+        cmd.env("RUSTFLAGS", rustflags);
+    } else {
+        // Cargo looks up the configuration of a custom target by the name of
+        // its JSON file.
+        let triple = match rust_target.strip_suffix(".json") {
+            Some(_) => Path::new(rust_target)
+                .file_stem()
+                .map_or(rust_target.into(), |stem| stem.to_string_lossy()),
+            None => rust_target.into(),
+        };
+        let config = format!("target.{:?}.rustflags={:?}", triple, RUSTFLAGS);
+        info!("Building with --config {}", config);
+        cmd.arg("--config").arg(config);
+
+        if let Some(source) = build_rustflags_source(path) {
+            PBAR.warn(&format!(
+                "The `build.rustflags` set in {} are ignored by threaded builds, because \
+                 cargo only uses `target.<triple>.rustflags` when any are set. Move them \
+                 to `[target.{}]` to keep them.",
+                source, triple
+            ));
+        }
+    }
+
+    let has_build_std = extra_options
+        .iter()
+        .any(|option| option.contains("build-std"));
+    if !has_build_std {
+        cmd.arg("-Z").arg("build-std=panic_abort,std");
+    }
+}
+
+/// Where `build.rustflags` is set for cargo builds of the crate at `path`, if
+/// it is: `CARGO_BUILD_RUSTFLAGS`, or one of the `.cargo/config.toml` files
+/// cargo reads.
+fn build_rustflags_source(path: &Path) -> Option<String> {
+    if env::var_os("CARGO_BUILD_RUSTFLAGS").is_some() {
+        return Some("CARGO_BUILD_RUSTFLAGS".to_string());
+    }
+    let cargo_home = env::var_os("CARGO_HOME").map(PathBuf::from).or_else(|| {
+        env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".cargo"))
+    });
+    let dirs = path
+        .ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .chain(cargo_home);
+    for dir in dirs {
+        for name in ["config.toml", "config"] {
+            let file = dir.join(name);
+            let sets_rustflags = fs::read_to_string(&file)
+                .ok()
+                .and_then(|contents| contents.parse::<toml::Value>().ok())
+                .is_some_and(|config| {
+                    config
+                        .get("build")
+                        .and_then(|build| build.get("rustflags"))
+                        .is_some()
+                });
+            if sets_rustflags {
+                return Some(file.display().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Ensure that the toolchain used for a threaded build of the crate at `path`
+/// has the `rust-src` component, which `-Z build-std` needs.
+pub fn check_for_rust_src(path: &Path) -> Result<()> {
+    let msg = format!("{}Checking for the Rust sources...", emoji::TARGET);
+    PBAR.info(&msg);
+
+    let toolchain = toolchain(path);
+    let sysroot = get_rustc_sysroot(path, toolchain)?;
+    let rust_src = sysroot.join("lib/rustlib/src/rust/library");
+    if rust_src.is_dir() {
+        info!("Found the Rust sources in {:?}", rust_src);
+        return Ok(());
+    }
+
+    if !sysroot.to_string_lossy().contains("rustup") {
+        bail!(
+            "Building with threads requires the Rust sources, but they were not found in {}.\n\
+             wasm-pack rebuilds the standard library with atomics enabled, which needs a \
+             nightly toolchain with the `rust-src` component installed.",
+            rust_src.display()
+        );
+    }
+
+    let mut cmd = Command::new("rustup");
+    cmd.current_dir(path)
+        .arg("component")
+        .arg("add")
+        .arg("rust-src");
+    if let Some(toolchain) = toolchain {
+        cmd.arg("--toolchain").arg(toolchain);
+    }
+    child::run(cmd, "rustup").context("Adding the rust-src component with rustup")?;
+    Ok(())
+}
+
+fn get_rustc_sysroot(path: &Path, toolchain: Option<&str>) -> Result<PathBuf> {
+    let mut cmd = Command::new("rustc");
+    cmd.current_dir(path);
+    if let Some(toolchain) = toolchain {
+        cmd.arg(format!("+{}", toolchain));
+    }
+    let output = cmd
+        .args(["--print", "sysroot"])
+        .output()
+        .context("Running rustc to find its sysroot")?;
+
+    if output.status.success() {
+        Ok(String::from_utf8(output.stdout)?.trim().into())
+    } else {
+        Err(anyhow!(
+            "Getting the sysroot of the {} toolchain wasn't successful. Got {}",
+            toolchain.unwrap_or("active"),
+            output.status
+        ))
+    }
+}
@@ -1,4 +1,4 @@
-//! Checking for the wasm32 target
+//! Checking for the wasm32 target, or whichever target is being compiled for
 
 use crate::child;
 use crate::emoji;
@@ -7,10 +7,47 @@ use anyhow::{anyhow, bail, Context, Result};
 use log::error;
 use log::info;
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// The target that is compiled for unless `--rust-target` says otherwise.
+pub const DEFAULT_RUST_TARGET: &str = "wasm32-unknown-unknown";
+
+/// Whether `rust_target` is the path to a custom target JSON file, rather
+/// than a target triple.
+pub fn is_custom_target(rust_target: &str) -> bool {
+    rust_target.ends_with(".json")
+}
+
+/// The name cargo uses for `rust_target` in the target directory: the triple
+/// itself, or the file name without `.json` for a custom target.
+pub fn rust_target_name(rust_target: &str) -> &str {
+    if is_custom_target(rust_target) {
+        Path::new(rust_target)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(rust_target)
+    } else {
+        rust_target
+    }
+}
+
+/// The environment variable that sets the runner cargo uses for binaries
+/// compiled for `rust_target`, such as
+/// `CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER`.
+pub fn runner_env_var(rust_target: &str) -> String {
+    let name: String = rust_target_name(rust_target)
+        .chars()
+        .map(|c| match c {
+            '-' | '.' => '_',
+            c => c.to_ascii_uppercase(),
+        })
+        .collect();
+    format!("CARGO_TARGET_{}_RUNNER", name)
+}
+
 struct Wasm32Check {
+    target: String,
     rustc_path: PathBuf,
     sysroot: PathBuf,
     found: bool,
@@ -19,7 +56,7 @@ struct Wasm32Check {
 
 impl fmt::Display for Wasm32Check {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let target = "wasm32-unknown-unknown";
+        let target = &self.target;
 
         if !self.found {
             let rustup_string = if self.is_rustup {
@@ -51,22 +88,38 @@ impl fmt::Display for Wasm32Check {
     }
 }
 
-/// Ensure that `rustup` has the `wasm32-unknown-unknown` target installed for
-/// current toolchain
-pub fn check_for_wasm32_target() -> Result<()> {
+/// Ensure that `rustup` has the `rust_target` target, usually
+/// `wasm32-unknown-unknown`, installed for current toolchain
+pub fn check_for_wasm32_target(rust_target: &str) -> Result<()> {
     let msg = format!("{}Checking for the Wasm target...", emoji::TARGET);
     PBAR.info(&msg);
 
+    // Custom targets aren't installed with rustup; their standard library is
+    // built from source, so there is nothing to check.
+    if is_custom_target(rust_target) {
+        info!(
+            "Skipping the target check for custom target {}",
+            rust_target
+        );
+        return Ok(());
+    }
+
     // Check if wasm32 target is present, otherwise bail.
-    match check_wasm32_target() {
+    match check_wasm32_target(rust_target) {
         Ok(ref wasm32_check) if wasm32_check.found => Ok(()),
         Ok(wasm32_check) => bail!("{}", wasm32_check),
         Err(err) => Err(err),
     }
 }
 
+/// Whether the standard library for `rust_target` is installed, without
+/// trying to install it. Custom targets are always considered installed.
+pub fn is_target_installed(rust_target: &str) -> bool {
+    is_custom_target(rust_target) || does_wasm32_target_libdir_exist(rust_target)
+}
+
 /// Get rustc's sysroot as a PathBuf
-fn get_rustc_sysroot() -> Result<PathBuf> {
+pub fn get_rustc_sysroot() -> Result<PathBuf> {
     let command = Command::new("rustc")
         .args(&["--print", "sysroot"])
         .output()?;
@@ -81,42 +134,35 @@ fn get_rustc_sysroot() -> Result<PathBuf> {
     }
 }
 
-/// Get wasm32-unknown-unknown target libdir
-fn get_rustc_wasm32_unknown_unknown_target_libdir() -> Result<PathBuf> {
+/// Get the target libdir of `rust_target`
+fn get_rustc_target_libdir(rust_target: &str) -> Result<PathBuf> {
     let command = Command::new("rustc")
-        .args(&[
-            "--target",
-            "wasm32-unknown-unknown",
-            "--print",
-            "target-libdir",
-        ])
+        .args(["--target", rust_target, "--print", "target-libdir"])
         .output()?;
 
     if command.status.success() {
         Ok(String::from_utf8(command.stdout)?.trim().into())
     } else {
         Err(anyhow!(
-            "Getting rustc's wasm32-unknown-unknown target wasn't successful. Got {}",
+            "Getting rustc's {} target wasn't successful. Got {}",
+            rust_target,
             command.status
         ))
     }
 }
 
-fn does_wasm32_target_libdir_exist() -> bool {
-    let result = get_rustc_wasm32_unknown_unknown_target_libdir();
+fn does_wasm32_target_libdir_exist(rust_target: &str) -> bool {
+    let result = get_rustc_target_libdir(rust_target);
 
     match result {
         Ok(wasm32_target_libdir_path) => {
             if wasm32_target_libdir_path.exists() {
-                info!(
-                    "Found wasm32-unknown-unknown in {:?}",
-                    wasm32_target_libdir_path
-                );
+                info!("Found {} in {:?}", rust_target, wasm32_target_libdir_path);
                 true
             } else {
                 info!(
-                    "Failed to find wasm32-unknown-unknown in {:?}",
-                    wasm32_target_libdir_path
+                    "Failed to find {} in {:?}",
+                    rust_target, wasm32_target_libdir_path
                 );
                 false
             }
@@ -128,12 +174,14 @@ fn does_wasm32_target_libdir_exist() -> bool {
     }
 }
 
-fn check_wasm32_target() -> Result<Wasm32Check> {
+fn check_wasm32_target(rust_target: &str) -> Result<Wasm32Check> {
     let sysroot = get_rustc_sysroot()?;
     let rustc_path = which::which("rustc")?;
+    let target = rust_target.to_string();
 
-    if does_wasm32_target_libdir_exist() {
+    if does_wasm32_target_libdir_exist(rust_target) {
         Ok(Wasm32Check {
+            target,
             rustc_path,
             sysroot,
             found: true,
@@ -142,9 +190,10 @@ fn check_wasm32_target() -> Result<Wasm32Check> {
     // If it doesn't exist, then we need to check if we're using rustup.
     } else {
         // If sysroot contains "rustup", then we can assume we're using rustup
-        // and use rustup to add the wasm32-unknown-unknown target.
+        // and use rustup to add the target.
         if sysroot.to_string_lossy().contains("rustup") {
-            rustup_add_wasm_target().map(|()| Wasm32Check {
+            rustup_add_wasm_target(rust_target).map(|()| Wasm32Check {
+                target,
                 rustc_path,
                 sysroot,
                 found: true,
@@ -152,6 +201,7 @@ fn check_wasm32_target() -> Result<Wasm32Check> {
             })
         } else {
             Ok(Wasm32Check {
+                target,
                 rustc_path,
                 sysroot,
                 found: false,
@@ -161,11 +211,12 @@ fn check_wasm32_target() -> Result<Wasm32Check> {
     }
 }
 
-/// Add wasm32-unknown-unknown using `rustup`.
-fn rustup_add_wasm_target() -> Result<()> {
+/// Add `rust_target` using `rustup`.
+fn rustup_add_wasm_target(rust_target: &str) -> Result<()> {
     let mut cmd = Command::new("rustup");
-    cmd.arg("target").arg("add").arg("wasm32-unknown-unknown");
-    child::run(cmd, "rustup").context("Adding the wasm32-unknown-unknown target with rustup")?;
+    cmd.arg("target").arg("add").arg(rust_target);
+    child::run(cmd, "rustup")
+        .with_context(|| format!("Adding the {} target with rustup", rust_target))?;
 
     Ok(())
 }
@@ -1,15 +1,453 @@
-//! Getting and configuring wasm-pack's binary cache.
+//! Getting, configuring and managing wasm-pack's binary cache.
+//!
+//! Every tool that wasm-pack downloads or `cargo install`s gets its own
+//! directory in the cache. Next to each directory, a hidden `.{name}.json`
+//! file records which tool and version it holds and when it was last used, so
+//! that old entries can be listed and pruned.
 
-use anyhow::Result;
+use crate::config;
+use crate::lock::{self, FileLock};
+use anyhow::{anyhow, bail, Context, Result};
 use binary_install::Cache;
-use std::env;
-use std::path::Path;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::debug;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
 
-/// Get wasm-pack's binary cache.
+/// The tools that wasm-pack caches, used to tell the tool and version apart
+/// in the names of entries that were cached before entries were recorded.
+const TOOLS: &[&str] = &[
+    "wasm-bindgen",
+    "wasm-opt",
+    "cargo-generate",
+    "chromedriver",
+    "geckodriver",
+];
+
+/// Get wasm-pack's binary cache, in the directory set by `WASM_PACK_CACHE` or
+/// the `cache-dir` config setting if there is one.
 pub fn get_wasm_pack_cache() -> Result<Cache> {
-    if let Ok(path) = env::var("WASM_PACK_CACHE") {
-        Ok(Cache::at(Path::new(&path)))
+    if let Some(cache_dir) = config::get().cache_dir() {
+        Ok(Cache::at(&cache_dir.value))
     } else {
         Cache::new("wasm-pack")
     }
 }
+
+/// How a cache entry was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntrySource {
+    /// A prebuilt binary was downloaded.
+    Download,
+    /// The tool was built with `cargo install`.
+    CargoInstall,
+    /// A download or `cargo install` was interrupted, and left this behind.
+    Partial,
+}
+
+impl fmt::Display for EntrySource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            EntrySource::Download => "download",
+            EntrySource::CargoInstall => "cargo install",
+            EntrySource::Partial => "partial",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// What is recorded about a cache entry when it is used.
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    tool: String,
+    version: Option<String>,
+    source: EntrySource,
+    last_used: u64,
+}
+
+/// A directory in the cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    /// The path of the directory.
+    pub path: PathBuf,
+    /// The tool that the directory holds.
+    pub tool: String,
+    /// The version of the tool, if known.
+    pub version: Option<String>,
+    /// How the directory was created.
+    pub source: EntrySource,
+    /// The size of everything in the directory, in bytes.
+    pub size: u64,
+    /// When the tool was last used, or the directory was last modified if
+    /// that isn't recorded. Serialized as seconds since the Unix epoch.
+    #[serde(serialize_with = "serialize_unix_time")]
+    pub last_used: SystemTime,
+}
+
+fn serialize_unix_time<S: serde::Serializer>(
+    time: &SystemTime,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    serializer.serialize_u64(secs)
+}
+
+impl Entry {
+    fn name(&self) -> &str {
+        self.path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    /// The file that is locked while the directory is created.
+    fn lock_path(&self) -> PathBuf {
+        let name = self.name().trim_start_matches('.');
+        self.path.with_file_name(format!(".{}.lock", name))
+    }
+
+    /// Remove the entry, unless another process holds its lock because it is
+    /// creating it. Returns whether the entry was removed.
+    fn remove_unless_locked(&self) -> Result<bool> {
+        match FileLock::try_exclusive(&self.lock_path())? {
+            Some(_lock) => self.remove().map(|()| true),
+            None => {
+                debug!("{} is locked by another process", self.path.display());
+                Ok(false)
+            }
+        }
+    }
+
+    /// Remove the directory, along with its record. The lock file is kept:
+    /// another process may have opened it already, and would lock a file that
+    /// nobody else can see if it was removed.
+    pub fn remove(&self) -> Result<()> {
+        fs::remove_dir_all(&self.path)
+            .with_context(|| anyhow!("failed to remove {}", self.path.display()))?;
+        let name = self.name().trim_start_matches('.');
+        if let Some(dir) = self.path.parent() {
+            drop(fs::remove_file(record_path(dir, name)));
+        }
+        Ok(())
+    }
+}
+
+fn record_path(cache_dir: &Path, name: &str) -> PathBuf {
+    cache_dir.join(format!(".{}.json", name))
+}
+
+/// Record that the cache entry at `path` holds `version` of `tool`, and that
+/// it was used just now. Failing to record it isn't an error, as the tool can
+/// still be used.
+pub fn record_use(path: &Path, tool: &str, version: Option<&str>, source: EntrySource) {
+    let (dir, name) = match (
+        path.parent(),
+        path.file_name().and_then(|name| name.to_str()),
+    ) {
+        (Some(dir), Some(name)) => (dir, name),
+        _ => return,
+    };
+    let record = Record {
+        tool: tool.to_string(),
+        version: version.map(|version| version.to_string()),
+        source,
+        last_used: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs()),
+    };
+    let result = serde_json::to_vec(&record)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| lock::write_atomic(&record_path(dir, name), json));
+    if let Err(e) = result {
+        debug!("failed to record the use of {}: {}", path.display(), e);
+    }
+}
+
+/// Every entry in the cache, sorted by tool and then by when it was last used,
+/// most recent first.
+pub fn entries(cache: &Cache) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let dir = match fs::read_dir(&cache.destination) {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(entries),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                anyhow!(
+                    "failed to read the cache at {}",
+                    cache.destination.display()
+                )
+            })
+        }
+    };
+    for dir_entry in dir {
+        let dir_entry = dir_entry?;
+        if !dir_entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = match dir_entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        entries.extend(read_entry(&cache.destination, &name, dir_entry.path())?);
+    }
+    entries.sort_by(|a, b| {
+        a.tool
+            .cmp(&b.tool)
+            .then_with(|| b.last_used.cmp(&a.last_used))
+    });
+    Ok(entries)
+}
+
+/// What the directory called `name` holds, if it is a cache entry: a
+/// directory of one of the tools that wasm-pack caches, or one that has a
+/// record. Anything else, such as a `.git` directory in a shared cache
+/// directory, is left alone.
+fn read_entry(cache_dir: &Path, name: &str, path: PathBuf) -> Result<Option<Entry>> {
+    let (tool, version, source, last_used) = match name.strip_prefix('.') {
+        // Interrupted downloads and `cargo install`s leave behind hidden
+        // directories named after the entry they were going to become.
+        Some(name) => match parse_name(name) {
+            Some((tool, version)) => (tool, version, EntrySource::Partial, None),
+            None => return Ok(None),
+        },
+        None => match fs::read(record_path(cache_dir, name))
+            .ok()
+            .and_then(|json| serde_json::from_slice::<Record>(&json).ok())
+        {
+            Some(record) => (
+                record.tool,
+                record.version,
+                record.source,
+                Some(UNIX_EPOCH + Duration::from_secs(record.last_used)),
+            ),
+            None => match parse_name(name) {
+                Some((tool, version)) => {
+                    let source = if name.contains("-cargo-install-") {
+                        EntrySource::CargoInstall
+                    } else {
+                        EntrySource::Download
+                    };
+                    (tool, version, source, None)
+                }
+                None => return Ok(None),
+            },
+        },
+    };
+
+    let mut size = 0;
+    let mut modified = SystemTime::UNIX_EPOCH;
+    for file in WalkDir::new(&path) {
+        let metadata = file?.metadata()?;
+        if metadata.is_file() {
+            size += metadata.len();
+        }
+        if let Ok(time) = metadata.modified() {
+            modified = modified.max(time);
+        }
+    }
+
+    Ok(Some(Entry {
+        path,
+        tool,
+        version,
+        source,
+        size,
+        last_used: last_used.unwrap_or(modified),
+    }))
+}
+
+/// Tell the tool and version apart in the name of an entry: either
+/// `{tool}-cargo-install-{version}`, `{tool}-{version}`, or `{tool}-{hash}`
+/// where the hash of the URL says nothing about the version. Returns `None` if
+/// the name doesn't start with one of the tools that wasm-pack caches.
+fn parse_name(name: &str) -> Option<(String, Option<String>)> {
+    let tool = TOOLS.iter().find(|tool| {
+        name.strip_prefix(**tool)
+            .is_some_and(|rest| rest.starts_with('-'))
+    })?;
+    let rest = name[tool.len()..].trim_start_matches('-');
+    let rest = rest.strip_prefix("cargo-install-").unwrap_or(rest);
+    let is_hash = rest.len() == 16 && rest.chars().all(|c| c.is_ascii_hexdigit());
+    let version = Some(rest)
+        .filter(|rest| !rest.is_empty() && !is_hash)
+        .map(|rest| rest.to_string());
+    Some((tool.to_string(), version))
+}
+
+/// How long ago a cache entry must have last been used for `cache prune
+/// --older-than` to remove it, such as `30d` or `12h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Age(pub Duration);
+
+impl std::str::FromStr for Age {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number
+            .parse()
+            .with_context(|| format!("invalid age `{}`", s))?;
+        let seconds = match unit.trim() {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => bail!(
+                "invalid age `{}`, expected a number followed by s, m, h, d or w",
+                s
+            ),
+        };
+        Ok(Age(Duration::from_secs(number * seconds)))
+    }
+}
+
+/// Remove the entries that were last used longer than `older_than` ago, except
+/// for the `keep_latest` most recently used entries of each tool, along with
+/// any partial entries. Returns the removed entries.
+pub fn prune(
+    cache: &Cache,
+    older_than: Option<Age>,
+    keep_latest: Option<usize>,
+) -> Result<Vec<Entry>> {
+    let now = SystemTime::now();
+    let mut removed = Vec::new();
+    let mut kept_of_tool = 0;
+    let mut previous_tool = None;
+    for entry in entries(cache)? {
+        if entry.source == EntrySource::Partial {
+            if entry.remove_unless_locked()? {
+                removed.push(entry);
+            }
+            continue;
+        }
+        if previous_tool.as_ref() != Some(&entry.tool) {
+            previous_tool = Some(entry.tool.clone());
+            kept_of_tool = 0;
+        }
+        let is_latest = keep_latest.is_some_and(|keep| kept_of_tool < keep);
+        let is_old = match older_than {
+            Some(Age(age)) => now
+                .duration_since(entry.last_used)
+                .is_ok_and(|since| since > age),
+            // Without `--older-than`, `--keep-latest` alone decides.
+            None => keep_latest.is_some(),
+        };
+        if is_old && !is_latest && entry.remove_unless_locked()? {
+            removed.push(entry);
+        } else {
+            kept_of_tool += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Remove every entry from the cache, except for the ones that another process
+/// is creating. Returns the removed entries.
+pub fn clean(cache: &Cache) -> Result<Vec<Entry>> {
+    let mut removed = Vec::new();
+    for entry in entries(cache)? {
+        if entry.remove_unless_locked()? {
+            removed.push(entry);
+        }
+    }
+    Ok(removed)
+}
+
+/// Write every complete entry of the cache, along with what is recorded about
+/// it, to a `.tar.gz` file at `tarball`, to be imported into the cache of
+/// another machine. Returns the exported entries.
+pub fn export(cache: &Cache, tarball: &Path) -> Result<Vec<Entry>> {
+    let entries: Vec<Entry> = entries(cache)?
+        .into_iter()
+        .filter(|entry| entry.source != EntrySource::Partial)
+        .collect();
+    let file =
+        File::create(tarball).with_context(|| anyhow!("failed to create {}", tarball.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for entry in &entries {
+        let name = entry.name();
+        builder.append_dir_all(name, &entry.path)?;
+        let record = record_path(&cache.destination, name);
+        if record.is_file() {
+            builder.append_path_with_name(&record, format!(".{}.json", name))?;
+        }
+    }
+    builder
+        .into_inner()?
+        .finish()
+        .with_context(|| anyhow!("failed to write {}", tarball.display()))?;
+    Ok(entries)
+}
+
+/// Add the entries of a `.tar.gz` file written by `export` to the cache.
+/// Entries that are already in the cache are kept as they are. Returns the
+/// names of the imported entries.
+pub fn import(cache: &Cache, tarball: &Path) -> Result<Vec<String>> {
+    let file =
+        File::open(tarball).with_context(|| anyhow!("failed to open {}", tarball.display()))?;
+
+    // Unpack everything into a temporary directory first, so that a broken
+    // tarball doesn't leave half of an entry in the cache.
+    let temp = cache.join(Path::new(&format!(".import-{}", std::process::id())));
+    drop(fs::remove_dir_all(&temp));
+    fs::create_dir_all(&temp)?;
+    let result = tar::Archive::new(GzDecoder::new(file))
+        .unpack(&temp)
+        .with_context(|| anyhow!("failed to unpack {}", tarball.display()))
+        .and_then(|()| move_imported(cache, &temp));
+    drop(fs::remove_dir_all(&temp));
+    result
+}
+
+fn move_imported(cache: &Cache, temp: &Path) -> Result<Vec<String>> {
+    let mut imported = Vec::new();
+    for dir_entry in fs::read_dir(temp)? {
+        let dir_entry = dir_entry?;
+        let name = match dir_entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        if !dir_entry.file_type()?.is_dir() || name.starts_with('.') {
+            continue;
+        }
+        let destination = cache.join(Path::new(&name));
+        let _lock = FileLock::exclusive(&cache.join(Path::new(&format!(".{}.lock", name))))?;
+        if destination.exists() {
+            debug!("{} is already in the cache", name);
+            continue;
+        }
+        let record = record_path(temp, &name);
+        if record.is_file() {
+            fs::rename(&record, record_path(&cache.destination, &name))?;
+        }
+        fs::rename(dir_entry.path(), &destination)?;
+        imported.push(name);
+    }
+    imported.sort();
+    Ok(imported)
+}
+
+/// The most recently used complete entry of `tool` in the cache whose
+/// recorded version `version_matches`, if any.
+pub fn latest_entry(
+    cache: &Cache,
+    tool: &str,
+    version_matches: impl Fn(&str) -> bool,
+) -> Option<Entry> {
+    entries(cache).ok()?.into_iter().find(|entry| {
+        entry.tool == tool
+            && entry.source != EntrySource::Partial
+            && entry.version.as_deref().is_some_and(&version_matches)
+    })
+}
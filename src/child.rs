@@ -4,9 +4,11 @@
 //! properly logged and their output is logged as well.
 
 use crate::install::Tool;
-use anyhow::{bail, Result};
+use crate::PBAR;
+use anyhow::{bail, Context, Result};
 use log::info;
-use std::process::{Command, Stdio};
+use std::io::{self, BufRead, BufReader};
+use std::process::{Command, ExitStatus, Stdio};
 
 /// Return a new Command object
 pub fn new_command(program: &str) -> Command {
@@ -24,11 +26,56 @@ pub fn new_command(program: &str) -> Command {
     }
 }
 
+/// Run the given command to completion and return its exit status.
+///
+/// With `--message-format json`, wasm-pack's stdout only carries JSON
+/// events, so whatever the command writes to stdout goes to stderr instead.
+pub fn status(command: &mut Command) -> Result<ExitStatus> {
+    if !PBAR.is_json() {
+        return Ok(command.status()?);
+    }
+    let mut child = command.stdout(Stdio::piped()).spawn()?;
+    let mut stdout = child.stdout.take().unwrap();
+    io::copy(&mut stdout, &mut io::stderr())?;
+    Ok(child.wait()?)
+}
+
 /// Run the given command and return on success.
 pub fn run(mut command: Command, command_name: &str) -> Result<()> {
     info!("Running {:?}", command);
 
-    let status = command.status()?;
+    let status = status(&mut command)?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        bail!(
+            "failed to execute `{}`: exited with {}\n  full command: {:?}",
+            command_name,
+            status,
+            command,
+        )
+    }
+}
+
+/// Run the given command and return on success, passing each line that it
+/// writes to stdout to `on_line` as soon as it is written.
+pub fn run_with_stdout_lines(
+    mut command: Command,
+    command_name: &str,
+    mut on_line: impl FnMut(&str),
+) -> Result<()> {
+    info!("Running {:?}", command);
+
+    let mut child = command
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start `{}`", command_name))?;
+    let stdout = child.stdout.take().unwrap();
+    for line in BufReader::new(stdout).lines() {
+        on_line(&line?);
+    }
+    let status = child.wait()?;
 
     if status.success() {
         Ok(())
@@ -2,14 +2,18 @@
 
 use crate::bindgen;
 use crate::build;
+use crate::build::wasm_target::{is_custom_target, DEFAULT_RUST_TARGET};
 use crate::cache;
-use crate::command::utils::{create_pkg_dir, get_crate_path};
+use crate::command::utils::{create_pkg_dir, get_crate_path, report_output_files, run_step};
 use crate::emoji;
+use crate::fingerprint::{self, Fingerprints, Record};
 use crate::install::{self, InstallMode, Tool};
 use crate::license;
 use crate::lockfile::Lockfile;
-use crate::manifest;
+use crate::manifest::{self, CargoWasmPack};
+use crate::progressbar::Event;
 use crate::readme;
+use crate::size::SizeReport;
 use crate::wasm_opt;
 use crate::PBAR;
 use anyhow::{anyhow, bail, Error, Result};
@@ -18,8 +22,10 @@ use clap::Args;
 use log::info;
 use path_clean::PathClean;
 use std::fmt;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::thread::{self, JoinHandle};
 use std::time::Instant;
 
 /// Everything required to configure and run the `wasm-pack build` command.
@@ -31,21 +37,46 @@ pub struct Build {
     pub disable_dts: bool,
     pub weak_refs: bool,
     pub reference_types: bool,
-    pub target: Target,
+    pub targets: Vec<Target>,
     pub no_pack: bool,
     pub no_opt: bool,
     pub profile: BuildProfile,
+    pub rust_target: String,
+    pub threads: bool,
     pub mode: InstallMode,
     pub out_dir: PathBuf,
     pub out_name: Option<String>,
     pub bindgen: Option<install::Status>,
     pub cache: Cache,
     pub extra_options: Vec<String>,
+    /// The `.wasm` file that cargo compiled, once the crate has been built.
+    wasm_path: Option<PathBuf>,
+    /// Targets whose bindings were left untouched because the compiled wasm
+    /// has not changed since they were generated.
+    fresh_targets: Vec<Target>,
+    /// Fingerprints of the bindings generated by this build, recorded once
+    /// `wasm-opt` has run as well.
+    pending_fingerprints: Vec<(Target, Record)>,
+    /// The tools being downloaded while cargo compiles the crate.
+    prefetch: Option<Prefetch>,
+    /// The `wasm-opt` that was looked for while cargo compiled the crate,
+    /// kept until `wasm-opt` runs so that errors are reported in step order.
+    wasm_opt: Option<Result<Option<PathBuf>>>,
+}
+
+/// The tools that are resolved and downloaded on background threads while
+/// cargo compiles the crate.
+struct Prefetch {
+    /// The version of `wasm-bindgen` in `Cargo.lock` before the build.
+    bindgen_version: String,
+    bindgen: JoinHandle<Result<install::Status>>,
+    /// `None` if `wasm-opt` doesn't run.
+    wasm_opt: Option<JoinHandle<Result<Option<PathBuf>>>>,
 }
 
 /// What sort of output we're going to be generating and flags we're invoking
 /// `wasm-bindgen` with.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Target {
     /// Default output mode or `--target bundler`, indicates output will be
     /// used with a bundle in a later step.
@@ -100,7 +131,7 @@ impl FromStr for Target {
 
 /// The build profile controls whether optimizations, debug info, and assertions
 /// are enabled or disabled.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum BuildProfile {
     /// Enable assertions and debug info. Disable optimizations.
     Dev,
@@ -113,7 +144,7 @@ pub enum BuildProfile {
 }
 
 /// Everything required to configure and run the `wasm-pack build` command.
-#[derive(Debug, Args)]
+#[derive(Clone, Debug, Args)]
 #[command(allow_hyphen_values = true, trailing_var_arg = true)]
 pub struct BuildOptions {
     /// The path to the Rust crate. If not set, searches up the path from the current directory.
@@ -128,22 +159,29 @@ pub struct BuildOptions {
     /// Sets steps to be run. [possible values: no-install, normal, force]
     pub mode: InstallMode,
 
-    #[clap(long = "no-typescript")]
+    #[clap(long = "no-typescript", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
     /// By default a *.d.ts file is generated for the generated JS file, but
-    /// this flag will disable generating this TypeScript file.
-    pub disable_dts: bool,
+    /// this flag will disable generating this TypeScript file. `=false`
+    /// overrides `no-typescript = true` in Cargo.toml.
+    pub disable_dts: Option<bool>,
 
-    #[clap(long = "weak-refs")]
-    /// Enable usage of the JS weak references proposal.
-    pub weak_refs: bool,
+    #[clap(long = "weak-refs", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Enable usage of the JS weak references proposal. `=false` overrides
+    /// `weak-refs = true` in Cargo.toml.
+    pub weak_refs: Option<bool>,
 
-    #[clap(long = "reference-types")]
-    /// Enable usage of WebAssembly reference types.
-    pub reference_types: bool,
+    #[clap(long = "reference-types", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Enable usage of WebAssembly reference types. `=false` overrides
+    /// `reference-types = true` in Cargo.toml.
+    pub reference_types: Option<bool>,
 
-    #[clap(long = "target", short = 't', default_value = "bundler")]
-    /// Sets the target environment. [possible values: bundler, nodejs, web, no-modules, deno]
-    pub target: Target,
+    #[clap(long = "target", short = 't', value_delimiter = ',')]
+    /// Sets the target environment. Defaults to `bundler`. [possible values: bundler, nodejs, web, no-modules, deno]
+    ///
+    /// Several comma-separated targets may be given, in which case each
+    /// target's bindings are generated into a sub-directory of the output
+    /// directory and share a single package.json.
+    pub target: Vec<Target>,
 
     #[clap(long = "debug")]
     /// Deprecated. Renamed to `--dev`.
@@ -166,21 +204,45 @@ pub struct BuildOptions {
     /// User-defined profile with --profile flag
     pub profile: Option<String>,
 
-    #[clap(long = "out-dir", short = 'd', default_value = "pkg")]
-    /// Sets the output directory with a relative path.
-    pub out_dir: String,
+    #[clap(long = "rust-target")]
+    /// The target triple to compile the crate for, such as `wasm32v1-none`
+    /// or `wasm32-wasip1`, or the path to a custom target JSON file. Defaults
+    /// to `wasm32-unknown-unknown`.
+    pub rust_target: Option<String>,
+
+    #[clap(long = "threads", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Build with atomics and shared memory, so that the wasm can be used from
+    /// several threads. Requires a nightly toolchain with `rust-src`. `=false`
+    /// overrides `threads = true` in the profile's Cargo.toml configuration.
+    pub threads: Option<bool>,
+
+    #[clap(long = "out-dir", short = 'd')]
+    /// Sets the output directory with a relative path. Defaults to `pkg`.
+    pub out_dir: Option<String>,
 
     #[clap(long = "out-name")]
     /// Sets the output file names. Defaults to package name.
     pub out_name: Option<String>,
 
-    #[clap(long = "no-pack", alias = "no-package")]
-    /// Option to not generate a package.json
-    pub no_pack: bool,
+    #[clap(long = "no-pack", alias = "no-package", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Option to not generate a package.json. `=false` overrides
+    /// `no-pack = true` in Cargo.toml.
+    pub no_pack: Option<bool>,
 
-    #[clap(long = "no-opt", alias = "no-optimization")]
-    /// Option to skip optimization with wasm-opt
-    pub no_opt: bool,
+    #[clap(long = "no-opt", alias = "no-optimization", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Option to skip optimization with wasm-opt. `=false` overrides
+    /// `no-opt = true` in Cargo.toml.
+    pub no_opt: Option<bool>,
+
+    #[clap(long = "workspace")]
+    /// Build every member of the workspace that has a `cdylib` target. Each
+    /// member's package is written to the output directory relative to that
+    /// member, where `{name}` in `--out-dir` is replaced by the package name.
+    pub workspace: bool,
+
+    #[clap(long = "package", short = 'p')]
+    /// Build only the given workspace member. May be given more than once.
+    pub package: Vec<String>,
 
     /// List of extra options to pass to `cargo build`
     pub extra_options: Vec<String>,
@@ -192,29 +254,85 @@ impl Default for BuildOptions {
             path: None,
             scope: None,
             mode: InstallMode::default(),
-            disable_dts: false,
-            weak_refs: false,
-            reference_types: false,
-            target: Target::default(),
+            disable_dts: None,
+            weak_refs: None,
+            reference_types: None,
+            target: Vec::new(),
             debug: false,
             dev: false,
-            no_pack: false,
-            no_opt: false,
+            no_pack: None,
+            no_opt: None,
+            workspace: false,
+            package: Vec::new(),
             release: false,
             profiling: false,
             profile: None,
-            out_dir: String::new(),
+            rust_target: None,
+            threads: None,
+            out_dir: None,
             out_name: None,
             extra_options: Vec::new(),
         }
     }
 }
 
+impl BuildOptions {
+    /// Fill in the options that were not given on the command line with the
+    /// project's defaults from `[package.metadata.wasm-pack]`. Flags given on
+    /// the command line, including `--no-opt=false` and the like, win.
+    pub fn with_defaults(mut self, defaults: &CargoWasmPack) -> BuildOptions {
+        if self.target.is_empty() {
+            self.target = defaults.target.clone().unwrap_or_default();
+        }
+        self.scope = self.scope.or_else(|| defaults.scope.clone());
+        self.out_dir = self.out_dir.or_else(|| defaults.out_dir.clone());
+        self.out_name = self.out_name.or_else(|| defaults.out_name.clone());
+        self.rust_target = self.rust_target.or_else(|| defaults.rust_target.clone());
+        self.disable_dts = self.disable_dts.or(defaults.no_typescript);
+        self.weak_refs = self.weak_refs.or(defaults.weak_refs);
+        self.reference_types = self.reference_types.or(defaults.reference_types);
+        self.no_pack = self.no_pack.or(defaults.no_pack);
+        self.no_opt = self.no_opt.or(defaults.no_opt);
+        if let Some(cargo_args) = &defaults.cargo_args {
+            self.extra_options.splice(0..0, cargo_args.iter().cloned());
+        }
+        self
+    }
+}
+
 type BuildStep = fn(&mut Build) -> Result<()>;
 
 impl Build {
     /// Construct a build command from the given options.
     pub fn try_from_opts(mut build_opts: BuildOptions) -> Result<Self> {
+        Build::move_path_to_extra_options(&mut build_opts);
+        let crate_path = get_crate_path(build_opts.path.clone())?;
+        let crate_data = manifest::CrateData::new(&crate_path, build_opts.out_name.clone())?;
+        Build::for_crate(&build_opts, crate_path, crate_data)
+    }
+
+    /// Construct one build command for each selected workspace member, as
+    /// given by `--workspace` and `--package`.
+    pub fn try_from_workspace_opts(mut build_opts: BuildOptions) -> Result<Vec<Self>> {
+        Build::move_path_to_extra_options(&mut build_opts);
+        let crate_path = get_crate_path(build_opts.path.clone())?;
+        let packages = if build_opts.workspace {
+            &[][..]
+        } else {
+            &build_opts.package[..]
+        };
+        manifest::CrateData::workspace_members(&crate_path, packages, build_opts.out_name.clone())?
+            .into_iter()
+            .map(|crate_data| {
+                let crate_path = crate_data.manifest_directory().to_path_buf();
+                Build::for_crate(&build_opts, crate_path, crate_data)
+            })
+            .collect()
+    }
+
+    /// The path is the first positional argument, so a cargo flag given
+    /// without a path ends up there instead of in the extra options.
+    fn move_path_to_extra_options(build_opts: &mut BuildOptions) {
         if let Some(path) = &build_opts.path {
             if path.to_string_lossy().starts_with("--") {
                 let path = build_opts.path.take().unwrap();
@@ -223,16 +341,30 @@ impl Build {
                     .insert(0, path.to_string_lossy().into_owned());
             }
         }
-        let crate_path = get_crate_path(build_opts.path)?;
-        let crate_data = manifest::CrateData::new(&crate_path, build_opts.out_name.clone())?;
-        let out_dir = crate_path.join(PathBuf::from(build_opts.out_dir)).clean();
+    }
+
+    fn for_crate(
+        build_opts: &BuildOptions,
+        crate_path: PathBuf,
+        crate_data: manifest::CrateData,
+    ) -> Result<Self> {
+        let build_opts = &build_opts
+            .clone()
+            .with_defaults(crate_data.build_defaults());
+
+        let out_dir = build_opts
+            .out_dir
+            .as_deref()
+            .unwrap_or("pkg")
+            .replace("{name}", crate_data.package_name());
+        let out_dir = crate_path.join(PathBuf::from(out_dir)).clean();
 
         let dev = build_opts.dev || build_opts.debug;
         let profile = match (
             dev,
             build_opts.release,
             build_opts.profiling,
-            build_opts.profile,
+            build_opts.profile.clone(),
         ) {
             (false, false, false, None) | (false, true, false, None) => BuildProfile::Release,
             (true, false, false, None) => BuildProfile::Dev,
@@ -243,23 +375,64 @@ impl Build {
             _ => bail!("Can only supply one of the --dev, --release, --profiling, or --profile 'name' flags"),
         };
 
+        crate_data.warn_for_legacy_custom_profile(&profile);
+
+        let mut targets: Vec<Target> = Vec::new();
+        for target in &build_opts.target {
+            if targets.contains(target) {
+                bail!("The target `{}` was given more than once", target);
+            }
+            targets.push(*target);
+        }
+        if targets.is_empty() {
+            targets.push(Target::default());
+        }
+
+        // Cargo runs inside the crate's directory, so a custom target JSON file
+        // given on the command line relative to the current directory has to
+        // be made absolute. The ones in `Cargo.toml` are already resolved
+        // against the directory of the manifest that sets them.
+        let rust_target = build_opts
+            .rust_target
+            .as_deref()
+            .unwrap_or(DEFAULT_RUST_TARGET);
+        let rust_target = if is_custom_target(rust_target) && Path::new(rust_target).is_relative() {
+            std::env::current_dir()?
+                .join(rust_target)
+                .to_string_lossy()
+                .into_owned()
+        } else {
+            rust_target.to_string()
+        };
+
+        let threads = build_opts
+            .threads
+            .unwrap_or_else(|| crate_data.configured_profile(profile.clone()).threads());
+
         Ok(Build {
             crate_path,
             crate_data,
-            scope: build_opts.scope,
-            disable_dts: build_opts.disable_dts,
-            weak_refs: build_opts.weak_refs,
-            reference_types: build_opts.reference_types,
-            target: build_opts.target,
-            no_pack: build_opts.no_pack,
-            no_opt: build_opts.no_opt,
+            scope: build_opts.scope.clone(),
+            disable_dts: build_opts.disable_dts.unwrap_or(false),
+            weak_refs: build_opts.weak_refs.unwrap_or(false),
+            reference_types: build_opts.reference_types.unwrap_or(false),
+            targets,
+            no_pack: build_opts.no_pack.unwrap_or(false),
+            no_opt: build_opts.no_opt.unwrap_or(false),
             profile,
+            rust_target,
+            threads,
             mode: build_opts.mode,
             out_dir,
-            out_name: build_opts.out_name,
+            out_name: build_opts.out_name.clone(),
             bindgen: None,
             cache: cache::get_wasm_pack_cache()?,
-            extra_options: build_opts.extra_options,
+            extra_options: build_opts.extra_options.clone(),
+            wasm_path: None,
+            fresh_targets: Vec::new(),
+            pending_fingerprints: Vec::new(),
+            prefetch: None,
+            wasm_opt: None,
         })
     }
 
@@ -270,13 +443,10 @@ impl Build {
 
     /// Execute this `Build` command.
     pub fn run(&mut self) -> Result<()> {
-        let process_steps = Build::get_process_steps(self.mode, self.no_pack, self.no_opt);
-
         let started = Instant::now();
 
-        for (_, process_step) in process_steps {
-            process_step(self)?;
-        }
+        self.run_process_steps()?;
+        report_output_files(&self.out_dir);
 
         let duration = crate::command::utils::elapsed(started.elapsed());
         info!("Done in {}.", &duration);
@@ -295,6 +465,99 @@ impl Build {
         Ok(())
     }
 
+    /// Run every step of this build, without reporting on the result.
+    pub(crate) fn run_process_steps(&mut self) -> Result<()> {
+        let process_steps = Build::get_process_steps(self.mode, self.no_pack, self.no_opt);
+        for (name, process_step) in process_steps {
+            run_step(name, || process_step(self))?;
+        }
+        Ok(())
+    }
+
+    /// Execute the builds of several workspace members, compiling the members
+    /// that are compiled the same way with a single `cargo build` invocation.
+    pub fn run_workspace(builds: &mut [Build]) -> Result<()> {
+        if builds.is_empty() {
+            bail!("There are no workspace members to build");
+        }
+        // Every step that any member runs, each of which is then only run
+        // for the members that need it.
+        let process_steps = Build::get_process_steps(
+            builds[0].mode,
+            builds.iter().all(|build| build.no_pack),
+            builds.iter().all(|build| build.no_opt),
+        );
+        let groups = Build::cargo_groups(builds);
+
+        let started = Instant::now();
+
+        for (name, process_step) in process_steps {
+            run_step(name, || match name {
+                "step_check_rustc_version" => process_step(&mut builds[0]),
+                // This only depends on the toolchain and the Rust target, so
+                // it is checked once for every cargo invocation.
+                "step_check_for_wasm_target" => groups
+                    .iter()
+                    .try_for_each(|group| process_step(&mut builds[group[0]])),
+                // The members share a `Cargo.lock`, so the other members find
+                // the tools that the first one downloads in the cache.
+                "step_prefetch_tools" => process_step(&mut builds[0]),
+                "step_build_wasm" => groups
+                    .iter()
+                    .try_for_each(|group| Build::build_workspace_wasm(builds, group)),
+                _ => builds
+                    .iter_mut()
+                    .filter(|build| build.runs_step(name))
+                    .try_for_each(|build| process_step(build)),
+            })?;
+        }
+
+        let duration = crate::command::utils::elapsed(started.elapsed());
+        info!("Done in {}.", &duration);
+        PBAR.info(&format!("{} Done in {}", emoji::SPARKLE, &duration));
+
+        for build in builds.iter() {
+            report_output_files(&build.out_dir);
+            info!(
+                "Your wasm pkg is ready to publish at {}.",
+                build.out_dir.display()
+            );
+            PBAR.info(&format!(
+                "{} Your wasm pkg is ready to publish at {}.",
+                emoji::PACKAGE,
+                build.out_dir.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether this build runs the step called `name` on its own.
+    fn runs_step(&self, name: &str) -> bool {
+        Build::get_process_steps(self.mode, self.no_pack, self.no_opt)
+            .iter()
+            .any(|(step, _)| *step == name)
+    }
+
+    /// Group `builds` into the ones that can be compiled by the same
+    /// `cargo build` invocation, as indices into `builds`.
+    fn cargo_groups(builds: &[Build]) -> Vec<Vec<usize>> {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (index, build) in builds.iter().enumerate() {
+            let compiles_alike = |group: &&mut Vec<usize>| {
+                let other = &builds[group[0]];
+                other.profile == build.profile
+                    && other.rust_target == build.rust_target
+                    && other.threads == build.threads
+                    && other.extra_options == build.extra_options
+            };
+            match groups.iter_mut().find(compiles_alike) {
+                Some(group) => group.push(index),
+                None => groups.push(vec![index]),
+            }
+        }
+        groups
+    }
+
     fn get_process_steps(
         mode: InstallMode,
         no_pack: bool,
@@ -323,6 +586,7 @@ impl Build {
         }
 
         steps.extend(steps![
+            step_prefetch_tools,
             step_build_wasm,
             step_create_dir,
             step_install_wasm_bindgen,
@@ -333,6 +597,8 @@ impl Build {
             steps.extend(steps![step_run_wasm_opt]);
         }
 
+        steps.extend(steps![step_record_fingerprints, step_report_size]);
+
         if !no_pack {
             steps.extend(steps![
                 step_create_json,
@@ -360,40 +626,119 @@ impl Build {
     }
 
     fn step_check_for_wasm_target(&mut self) -> Result<()> {
+        if self.threads {
+            // Threaded builds rebuild the standard library from source, so
+            // they need the sources rather than a prebuilt target.
+            info!("Checking for rust-src...");
+            build::threads::check_for_rust_src(&self.crate_path)?;
+            info!("Checking for rust-src was successful.");
+            return Ok(());
+        }
         info!("Checking for wasm-target...");
-        build::wasm_target::check_for_wasm32_target()?;
+        build::wasm_target::check_for_wasm32_target(&self.rust_target)?;
         info!("Checking for wasm-target was successful.");
         Ok(())
     }
 
     fn step_build_wasm(&mut self) -> Result<()> {
         info!("Building wasm...");
-        build::cargo_build_wasm(&self.crate_path, self.profile.clone(), &self.extra_options)?;
+        let artifacts = build::cargo_build_wasm(
+            &self.crate_path,
+            self.profile.clone(),
+            &self.rust_target,
+            self.threads,
+            &self.extra_options,
+        )?;
+        self.set_wasm_path(&artifacts)?;
 
-        info!(
-            "wasm built at {:#?}.",
-            &self
-                .crate_path
-                .join("target")
-                .join("wasm32-unknown-unknown")
-                .join("release")
-        );
+        info!("wasm built at {:#?}.", self.wasm_path);
+        Ok(())
+    }
+
+    /// Build the wasm of the `group` of `builds` with a single cargo
+    /// invocation.
+    fn build_workspace_wasm(builds: &mut [Build], group: &[usize]) -> Result<()> {
+        let packages: Vec<&str> = group
+            .iter()
+            .map(|&index| builds[index].crate_data.package_name())
+            .collect();
+        info!("Building wasm for workspace members {:?}...", packages);
+        let first = &builds[group[0]];
+        let mut extra_options = Vec::new();
+        for package in &packages {
+            extra_options.push("--package".to_string());
+            extra_options.push(package.to_string());
+        }
+        extra_options.extend(first.extra_options.iter().cloned());
+        let artifacts = build::cargo_build_wasm(
+            &first.crate_path,
+            first.profile.clone(),
+            &first.rust_target,
+            first.threads,
+            &extra_options,
+        )?;
+        info!("wasm built for workspace members {:?}.", packages);
+        for &index in group {
+            builds[index].set_wasm_path(&artifacts)?;
+        }
+        Ok(())
+    }
+
+    /// Pick this crate's `.wasm` file out of the `artifacts` cargo compiled.
+    fn set_wasm_path(&mut self, artifacts: &[build::WasmArtifact]) -> Result<()> {
+        let artifact = artifacts
+            .iter()
+            .find(|artifact| &artifact.package_id == self.crate_data.package_id())
+            .ok_or_else(|| {
+                anyhow!(
+                    "cargo did not report a `.wasm` file for the `cdylib` target of `{}`",
+                    self.crate_data.package_name()
+                )
+            })?;
+        self.wasm_path = Some(artifact.path.clone());
         Ok(())
     }
 
+    /// The `.wasm` file that cargo compiled.
+    fn wasm_path(&self) -> Result<&Path> {
+        self.wasm_path
+            .as_deref()
+            .ok_or_else(|| anyhow!("the crate has not been compiled to wasm yet"))
+    }
+
     fn step_create_dir(&mut self) -> Result<()> {
         info!("Creating a pkg directory...");
         create_pkg_dir(&self.out_dir)?;
+        if self.targets.len() > 1 {
+            for (_, target_dir) in self.target_out_dirs() {
+                // Clean up the package.json from previous runs, as wasm-bindgen
+                // may or may not write a new one.
+                let _ = fs::remove_file(target_dir.join("package.json"));
+                fs::create_dir_all(&target_dir)?;
+            }
+        }
         info!("Created a pkg directory at {:#?}.", &self.crate_path);
         Ok(())
     }
 
+    /// The directories that each target's bindings are generated into. A
+    /// single target is generated directly into the output directory.
+    fn target_out_dirs(&self) -> Vec<(Target, PathBuf)> {
+        if self.targets.len() == 1 {
+            return vec![(self.targets[0], self.out_dir.clone())];
+        }
+        self.targets
+            .iter()
+            .map(|target| (*target, self.out_dir.join(target.to_string())))
+            .collect()
+    }
+
     fn step_create_json(&mut self) -> Result<()> {
         self.crate_data.write_package_json(
             &self.out_dir,
             &self.scope,
             self.disable_dts,
-            self.target,
+            &self.targets,
         )?;
         info!(
             "Wrote a package.json at {:#?}.",
@@ -416,10 +761,68 @@ impl Build {
         Ok(())
     }
 
+    fn step_prefetch_tools(&mut self) -> Result<()> {
+        // Without a `Cargo.lock` the version of wasm-bindgen isn't known until
+        // cargo has compiled the crate, so it is installed afterwards instead.
+        let lockfile = match Lockfile::new(&self.crate_data) {
+            Ok(lockfile) => lockfile,
+            Err(_) => return Ok(()),
+        };
+        let bindgen_version = match lockfile.wasm_bindgen_version() {
+            Some(version) => version.to_string(),
+            None => return Ok(()),
+        };
+        info!(
+            "Downloading wasm-bindgen {} during the build...",
+            bindgen_version
+        );
+
+        let install_permitted = self.mode.install_permitted();
+        let cache_dir = self.cache.destination.clone();
+        let version = bindgen_version.clone();
+        let bindgen = thread::spawn(move || {
+            install::download_prebuilt_or_cargo_install(
+                Tool::WasmBindgen,
+                &Cache::at(&cache_dir),
+                &version,
+                install_permitted,
+            )
+        });
+        let wasm_opt = self.wasm_opt_args().map(|_| {
+            let cache_dir = self.cache.destination.clone();
+            let version = self.crate_data.build_defaults().wasm_opt_version.clone();
+            thread::spawn(move || {
+                wasm_opt::find_wasm_opt(
+                    &Cache::at(&cache_dir),
+                    version.as_deref(),
+                    install_permitted,
+                )
+            })
+        });
+        self.prefetch = Some(Prefetch {
+            bindgen_version,
+            bindgen,
+            wasm_opt,
+        });
+        Ok(())
+    }
+
     fn step_install_wasm_bindgen(&mut self) -> Result<()> {
         info!("Identifying wasm-bindgen dependency...");
         let lockfile = Lockfile::new(&self.crate_data)?;
         let bindgen_version = lockfile.require_wasm_bindgen()?;
+
+        if let Some(prefetch) = self.prefetch.take() {
+            self.wasm_opt = prefetch.wasm_opt.map(|wasm_opt| join(wasm_opt, "wasm-opt"));
+            let bindgen = join(prefetch.bindgen, "wasm-bindgen");
+            // The build may have updated `Cargo.lock`.
+            if prefetch.bindgen_version == bindgen_version {
+                self.bindgen = Some(bindgen?);
+                info!("Installing wasm-bindgen-cli was successful.");
+                return Ok(());
+            }
+        }
+
         info!("Installing wasm-bindgen-cli...");
         let bindgen = install::download_prebuilt_or_cargo_install(
             Tool::WasmBindgen,
@@ -434,44 +837,251 @@ impl Build {
 
     fn step_run_wasm_bindgen(&mut self) -> Result<()> {
         info!("Building the wasm bindings...");
-        bindgen::wasm_bindgen_build(
+        let mut fingerprints = Fingerprints::load(&self.out_dir);
+        self.fresh_targets.clear();
+        self.pending_fingerprints.clear();
+        let dropped = fingerprints.retain(&self.targets);
+        if !dropped.is_empty() {
+            for record in &dropped {
+                record.remove_outputs(&self.out_dir);
+            }
+            fingerprints.save(&self.out_dir)?;
+        }
+        for (target, out_dir) in self.target_out_dirs() {
+            let fingerprint = self.fingerprint(target)?;
+            let fresh = fingerprints
+                .get(target)
+                .filter(|record| record.fingerprint == fingerprint && has_wasm(&out_dir));
+            if let Some(record) = fresh {
+                // `step_create_dir` removed the `package.json` that
+                // wasm-bindgen wrote, so put it back.
+                if let Some(deps) = &record.npm_dependencies {
+                    fs::write(
+                        out_dir.join("package.json"),
+                        serde_json::to_string_pretty(deps)?,
+                    )?;
+                }
+                info!("The wasm for target `{}` has not changed.", target);
+                PBAR.info(&format!(
+                    "The compiled wasm has not changed, skipping wasm-bindgen and wasm-opt for target `{}`",
+                    target
+                ));
+                self.fresh_targets.push(target);
+                continue;
+            }
+
+            // Forget the old fingerprint before touching the outputs, so that
+            // an interrupted build is never mistaken for a fresh one. The old
+            // outputs go too, in case they are named differently this time.
+            if let Some(record) = fingerprints.remove(target) {
+                fingerprints.save(&self.out_dir)?;
+                record.remove_outputs(&self.out_dir);
+            }
+            bindgen::wasm_bindgen_build(
+                &self.crate_data,
+                self.bindgen.as_ref().unwrap(),
+                self.wasm_path()?,
+                &out_dir,
+                &self.out_name,
+                self.disable_dts,
+                self.weak_refs,
+                self.reference_types,
+                target,
+                self.profile.clone(),
+            )?;
+            info!("wasm bindings were built at {:#?}.", &out_dir);
+
+            let npm_dependencies =
+                manifest::CrateData::read_wasm_bindgen_deps(&out_dir.join("package.json"))?;
+            let outputs = self.bindgen_outputs(&out_dir)?;
+            self.pending_fingerprints.push((
+                target,
+                Record {
+                    fingerprint,
+                    npm_dependencies,
+                    outputs,
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// The files and directories in `dir` that `wasm-bindgen` generates,
+    /// relative to the output directory: the `.js`, `.wasm` and `.d.ts` files
+    /// named after the output name, and the `snippets` directory.
+    fn bindgen_outputs(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let name_prefix = self.crate_data.name_prefix();
+        let mut outputs = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let generated = name
+                .strip_prefix(&name_prefix)
+                .is_some_and(|rest| rest.starts_with('.') || rest.starts_with("_bg."));
+            if generated || name == "snippets" {
+                outputs.push(path.strip_prefix(&self.out_dir)?.to_path_buf());
+            }
+        }
+        outputs.sort();
+        Ok(outputs)
+    }
+
+    /// Fingerprint everything that goes into generating the bindings for
+    /// `target`.
+    fn fingerprint(&self, target: Target) -> Result<String> {
+        let bindgen_path =
+            install::get_tool_path(self.bindgen.as_ref().unwrap(), Tool::WasmBindgen)?;
+        let wasm_bindgen_version = install::get_cli_version(&Tool::WasmBindgen, &bindgen_path)?;
+        let wasm_bindgen_flags = bindgen::wasm_bindgen_flags(
             &self.crate_data,
-            self.bindgen.as_ref().unwrap(),
-            &self.out_dir,
             &self.out_name,
             self.disable_dts,
             self.weak_refs,
             self.reference_types,
-            self.target,
             self.profile.clone(),
-            &self.extra_options,
-        )?;
-        info!("wasm bindings were built at {:#?}.", &self.out_dir);
-        Ok(())
+        );
+        let wasm_opt_args = self.wasm_opt_args();
+        fingerprint::Inputs {
+            wasm: self.wasm_path()?,
+            wasm_bindgen_version: &wasm_bindgen_version,
+            wasm_bindgen_flags: &wasm_bindgen_flags,
+            wasm_opt_args: wasm_opt_args.as_deref(),
+            wasm_opt_version: self.crate_data.build_defaults().wasm_opt_version.as_deref(),
+            target,
+        }
+        .fingerprint()
     }
 
-    fn step_run_wasm_opt(&mut self) -> Result<()> {
-        let mut args = match self
+    /// The arguments that `wasm-opt` runs with, or `None` if it doesn't run.
+    fn wasm_opt_args(&self) -> Option<Vec<String>> {
+        if self.no_opt {
+            return None;
+        }
+        let mut args = self
             .crate_data
             .configured_profile(self.profile.clone())
-            .wasm_opt_args()
-        {
+            .wasm_opt_args()?;
+        if self.reference_types {
+            args.push("--enable-reference-types".into());
+        }
+        if self.threads {
+            args.push("--enable-threads".into());
+        }
+        Some(args)
+    }
+
+    fn step_run_wasm_opt(&mut self) -> Result<()> {
+        let args = match self.wasm_opt_args() {
             Some(args) => args,
             None => return Ok(()),
         };
-        if self.reference_types {
-            args.push("--enable-reference-types".into());
+        let out_dirs: Vec<PathBuf> = self
+            .target_out_dirs()
+            .into_iter()
+            .filter(|(target, _)| !self.fresh_targets.contains(target))
+            .map(|(_, out_dir)| out_dir)
+            .collect();
+        if out_dirs.is_empty() {
+            return Ok(());
         }
-        info!("executing wasm-opt with {:?}", args);
-        wasm_opt::run(
-            &self.cache,
-            &self.out_dir,
-            &args,
-            self.mode.install_permitted(),
-        ).map_err(|e| {
+
+        let hint = |e: Error| {
             anyhow!(
                 "{}\nTo disable `wasm-opt`, add `wasm-opt = false` to your package metadata in your `Cargo.toml`.", e
             )
-        })
+        };
+        let wasm_opt = match self.wasm_opt.take() {
+            Some(wasm_opt) => wasm_opt,
+            None => wasm_opt::find_wasm_opt(
+                &self.cache,
+                self.crate_data.build_defaults().wasm_opt_version.as_deref(),
+                self.mode.install_permitted(),
+            ),
+        };
+        let wasm_opt_path = match wasm_opt.map_err(hint)? {
+            Some(path) => path,
+            // `find_wasm_opt` will have already logged a message about this, so we don't need to here.
+            None => return Ok(()),
+        };
+        install::report_tool("wasm-opt", &wasm_opt_path, None);
+
+        info!("executing wasm-opt with {:?}", args);
+        for out_dir in out_dirs {
+            wasm_opt::run(&wasm_opt_path, &out_dir, &args).map_err(hint)?;
+        }
+        Ok(())
+    }
+
+    fn step_report_size(&mut self) -> Result<()> {
+        info!("Measuring the size of the generated files...");
+        let budget = self
+            .crate_data
+            .configured_profile(self.profile.clone())
+            .size_budget();
+        let mut exceeded = Vec::new();
+        for (target, out_dir) in self.target_out_dirs() {
+            // The budget is checked against the same sizes as `wasm-pack
+            // size` reports, but measuring them takes a while on large builds.
+            let report = match budget {
+                Some(_) => SizeReport::new(&out_dir)?,
+                None => SizeReport::quick(&out_dir)?,
+            };
+            PBAR.event(&Event::SizeReport {
+                path: &out_dir,
+                files: &report.files,
+                modules: &report.modules,
+            });
+            PBAR.info(&format!(
+                "{}Size of {}:\n{}",
+                emoji::MEMO,
+                out_dir.display(),
+                report.table()
+            ));
+            if let Some(budget) = budget {
+                for limit in report.exceeded(budget) {
+                    if self.targets.len() > 1 {
+                        exceeded.push(format!("{} ({})", limit, target));
+                    } else {
+                        exceeded.push(limit);
+                    }
+                }
+            }
+        }
+        if !exceeded.is_empty() {
+            bail!(
+                "The generated package exceeds the size budget in your `Cargo.toml`:\n  {}",
+                exceeded.join("\n  ")
+            );
+        }
+        Ok(())
+    }
+
+    fn step_record_fingerprints(&mut self) -> Result<()> {
+        if self.pending_fingerprints.is_empty() {
+            return Ok(());
+        }
+        let mut fingerprints = Fingerprints::load(&self.out_dir);
+        for (target, record) in self.pending_fingerprints.drain(..) {
+            fingerprints.insert(target, record);
+        }
+        fingerprints.save(&self.out_dir)?;
+        info!("Recorded the fingerprints of the wasm bindings.");
+        Ok(())
     }
 }
+
+/// Wait for a tool that is being downloaded on a background thread.
+fn join<T>(handle: JoinHandle<Result<T>>, tool: &str) -> Result<T> {
+    handle
+        .join()
+        .map_err(|_| anyhow!("The download of {} panicked", tool))?
+}
+
+/// Whether `dir` contains a `.wasm` file generated by an earlier build.
+fn has_wasm(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.path().extension().is_some_and(|ext| ext == "wasm"))
+    })
+}
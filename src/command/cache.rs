@@ -0,0 +1,111 @@
+//! Implementation of the `wasm-pack cache` commands.
+
+use crate::cache::{self, Age, Entry};
+use crate::progressbar::Event;
+use crate::size::format_bytes;
+use crate::PBAR;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use clap::Subcommand;
+
+/// The `wasm-pack cache` subcommands.
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// 📋  list the cached tools with their version, size, source and last use
+    #[clap(name = "list")]
+    List,
+
+    /// ✂️  remove cached tools that haven't been used for a while
+    #[clap(name = "prune")]
+    Prune {
+        #[clap(long = "older-than")]
+        /// Remove the tools that were last used longer ago than this, such as `30d`, `12h` or `2w`
+        older_than: Option<Age>,
+
+        #[clap(long = "keep-latest")]
+        /// Keep this many of the most recently used versions of each tool
+        keep_latest: Option<usize>,
+    },
+
+    /// 🧹  remove every cached tool
+    #[clap(name = "clean")]
+    Clean,
+
+    /// 📁  print the path of the cache
+    #[clap(name = "path")]
+    Path,
+}
+
+/// Run a `wasm-pack cache` subcommand.
+pub fn cache(command: CacheCommand) -> Result<()> {
+    let cache = cache::get_wasm_pack_cache()?;
+    match command {
+        CacheCommand::List => {
+            let entries = cache::entries(&cache)?;
+            if PBAR.is_json() {
+                PBAR.event(&Event::CacheList { entries: &entries });
+            } else {
+                list(&entries);
+            }
+        }
+        CacheCommand::Prune {
+            older_than,
+            keep_latest,
+        } => report_removed(&cache::prune(&cache, older_than, keep_latest)?),
+        CacheCommand::Clean => report_removed(&cache::clean(&cache)?),
+        CacheCommand::Path => {
+            if PBAR.is_json() {
+                PBAR.event(&Event::CachePath {
+                    path: &cache.destination,
+                });
+            } else {
+                println!("{}", cache.destination.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn list(entries: &[Entry]) {
+    let rows: Vec<[String; 5]> = entries
+        .iter()
+        .map(|entry| {
+            [
+                entry.tool.clone(),
+                entry.version.clone().unwrap_or_else(|| "-".to_string()),
+                format_bytes(entry.size),
+                entry.source.to_string(),
+                DateTime::<Local>::from(entry.last_used)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+            ]
+        })
+        .collect();
+    let header = ["TOOL", "VERSION", "SIZE", "SOURCE", "LAST USED"].map(String::from);
+    let mut widths = [0; 5];
+    for row in rows.iter().chain(Some(&header)) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in Some(&header).into_iter().chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+fn report_removed(removed: &[Entry]) {
+    for entry in removed {
+        PBAR.info(&format!("Removed {}", entry.path.display()));
+    }
+    let size: u64 = removed.iter().map(|entry| entry.size).sum();
+    PBAR.info(&format!(
+        "Removed {} cache entries, freeing {}",
+        removed.len(),
+        format_bytes(size)
+    ));
+}
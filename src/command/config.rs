@@ -0,0 +1,41 @@
+//! Implementation of the `wasm-pack config` commands.
+
+use crate::config;
+use crate::progressbar::Event;
+use crate::PBAR;
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+
+/// The `wasm-pack config` subcommands.
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// 📋  list the effective settings and where each of them comes from
+    #[clap(name = "list")]
+    List,
+}
+
+/// Run a `wasm-pack config` subcommand.
+pub fn config(command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::List => list(),
+    }
+}
+
+/// List the effective settings. If a config file is invalid, the settings are
+/// listed without it, and the command fails with the reason.
+fn list() -> Result<()> {
+    let settings = config::get().list();
+    if PBAR.is_json() {
+        PBAR.event(&Event::ConfigList {
+            settings: &settings,
+        });
+    } else {
+        for setting in &settings {
+            println!("{} = {}  # {}", setting.key, setting.value, setting.source);
+        }
+    }
+    match config::load_error() {
+        Some(e) => Err(anyhow!("{:#}", e)),
+        None => Ok(()),
+    }
+}
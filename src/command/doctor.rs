@@ -0,0 +1,44 @@
+//! Implementation of the `wasm-pack doctor` command.
+
+use crate::cache;
+use crate::command::utils::get_crate_path;
+use crate::doctor::{self, Status};
+use crate::progressbar::Event;
+use crate::PBAR;
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+
+/// Check the environment, and report what is missing or broken along with how
+/// to fix it. Fails if anything that building needs is broken.
+pub fn doctor(path: Option<PathBuf>) -> Result<()> {
+    let crate_path = get_crate_path(path)?;
+    let crate_path = Some(crate_path).filter(|path| path.join("Cargo.toml").is_file());
+    let cache = cache::get_wasm_pack_cache()?;
+    let checks = doctor::checks(crate_path.as_deref(), &cache);
+
+    if PBAR.is_json() {
+        PBAR.event(&Event::DoctorReport { checks: &checks });
+    } else {
+        for check in &checks {
+            let message = format!("{}: {}", check.name, check.message);
+            let message = match &check.fix {
+                Some(fix) => format!("{}\n    Fix: {}", message, fix),
+                None => message,
+            };
+            match check.status {
+                Status::Pass => PBAR.info(&format!("{} {}", check.status, message)),
+                Status::Warn => PBAR.warn(&message),
+                Status::Fail => PBAR.error(&message),
+            }
+        }
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count();
+    if failed > 0 {
+        bail!("{} of {} checks failed", failed, checks.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Implementation of the `wasm-pack fetch` command.
+
+use crate::cache;
+use crate::command::utils::get_crate_path;
+use crate::emoji;
+use crate::install::{self, Tool};
+use crate::lockfile::Lockfile;
+use crate::manifest::CrateData;
+use crate::test::webdriver;
+use crate::wasm_opt;
+use crate::PBAR;
+use anyhow::{anyhow, Result};
+use binary_install::Cache;
+use clap::Args;
+use log::info;
+use std::path::{Path, PathBuf};
+
+/// Options for `wasm-pack fetch`.
+#[derive(Debug, Args)]
+pub struct FetchOptions {
+    #[clap(long = "chrome")]
+    /// Also fetch chromedriver, for `wasm-pack test --chrome`
+    pub chrome: bool,
+
+    #[clap(long = "firefox")]
+    /// Also fetch geckodriver, for `wasm-pack test --firefox`
+    pub firefox: bool,
+
+    #[clap(long = "export")]
+    /// Write the cache to this `.tar.gz` file after fetching, to import it on another machine
+    pub export: Option<PathBuf>,
+
+    #[clap(long = "import", conflicts_with_all = ["chrome", "firefox", "export", "path"])]
+    /// Add the tools in this `.tar.gz` file, written by `--export`, to the cache instead of fetching
+    pub import: Option<PathBuf>,
+
+    /// The path to the Rust crate. If not set, searches up the path from the current directory.
+    #[clap()]
+    pub path: Option<PathBuf>,
+}
+
+/// Download the tools that building and testing the crate need into the cache,
+/// so that later commands don't need network access.
+pub fn fetch(options: FetchOptions) -> Result<()> {
+    let cache = cache::get_wasm_pack_cache()?;
+    if let Some(tarball) = &options.import {
+        return import(&cache, tarball);
+    }
+
+    let crate_path = get_crate_path(options.path)?;
+    let crate_data = CrateData::new(&crate_path, None)?;
+    let lockfile = Lockfile::new(&crate_data)?;
+    let bindgen_version = lockfile.require_wasm_bindgen()?;
+    let status = install::install_to_cache(Tool::WasmBindgen, &cache, bindgen_version, true)?;
+    let path = install::get_tool_path(&status, Tool::WasmBindgen)?;
+    info!("wasm-bindgen {} is cached at {:?}", bindgen_version, path);
+
+    let wasm_opt_version = crate_data
+        .build_defaults()
+        .wasm_opt_version
+        .as_deref()
+        .unwrap_or("latest");
+    match wasm_opt::install(&cache, wasm_opt_version, true) {
+        Ok(install::Status::Found(download)) => {
+            info!("wasm-opt is cached at {:?}", download.path())
+        }
+        Ok(_) => PBAR.warn("wasm-opt could not be fetched."),
+        Err(e) => PBAR.warn(&format!("wasm-opt could not be fetched: {:#}", e)),
+    }
+
+    if options.chrome {
+        PBAR.info("Fetching chromedriver...");
+        webdriver::install_chromedriver(&cache, true)?;
+    }
+    if options.firefox {
+        PBAR.info("Fetching geckodriver...");
+        webdriver::install_geckodriver(&cache, true)?;
+    }
+
+    PBAR.info(&format!(
+        "{} The tools are cached at {}",
+        emoji::PACKAGE,
+        cache.destination.display()
+    ));
+
+    if let Some(tarball) = &options.export {
+        let exported = cache::export(&cache, tarball)?;
+        PBAR.info(&format!(
+            "Exported {} cache entries to {}. Import them with `wasm-pack fetch --import {}`.",
+            exported.len(),
+            tarball.display(),
+            tarball.display()
+        ));
+    }
+    Ok(())
+}
+
+fn import(cache: &Cache, tarball: &Path) -> Result<()> {
+    if !tarball.is_file() {
+        return Err(anyhow!("{} does not exist", tarball.display()));
+    }
+    let imported = cache::import(cache, tarball)?;
+    for name in &imported {
+        info!("Imported {}", name);
+    }
+    PBAR.info(&format!(
+        "{} Imported {} cache entries into {}",
+        emoji::PACKAGE,
+        imported.len(),
+        cache.destination.display()
+    ));
+    Ok(())
+}
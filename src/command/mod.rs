@@ -2,25 +2,40 @@
 #![allow(clippy::redundant_closure)]
 
 pub mod build;
+mod cache;
+mod config;
+mod doctor;
+mod fetch;
 mod generate;
 mod login;
 mod pack;
 /// Data structures and functions for publishing a package.
 pub mod publish;
+mod self_command;
+mod size;
 pub mod test;
 pub mod utils;
+mod watch;
 
 use self::build::{Build, BuildOptions};
+use self::cache::{cache, CacheCommand};
+use self::config::{config, ConfigCommand};
+use self::doctor::doctor;
+use self::fetch::{fetch, FetchOptions};
 use self::generate::generate;
 use self::login::login;
 use self::pack::pack;
 use self::publish::{access::Access, publish};
+use self::self_command::{self_command, SelfCommand};
+use self::size::size;
 use self::test::{Test, TestOptions};
+use self::watch::watch;
 use crate::install::InstallMode;
+use crate::size::MaxGrowth;
 use anyhow::Result;
 use clap::Subcommand;
 use log::info;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 /// The various kinds of commands that `wasm-pack` can execute.
 #[derive(Debug, Subcommand)]
 pub enum Command {
@@ -28,6 +43,10 @@ pub enum Command {
     #[clap(name = "build", alias = "init")]
     Build(BuildOptions),
 
+    #[clap(name = "watch")]
+    /// 👀  build your npm package, and rebuild it whenever the sources change
+    Watch(BuildOptions),
+
     #[clap(name = "pack")]
     /// 🍱  create a tar of your npm package but don't publish!
     Pack {
@@ -40,6 +59,34 @@ pub enum Command {
         path: Option<PathBuf>,
     },
 
+    #[clap(name = "size")]
+    /// 📏  report the size of your npm package, and compare it to a baseline
+    Size {
+        #[clap(long = "pkg-dir", short = 'd', default_value = "pkg")]
+        /// The name of the output directory where the npm package is stored
+        pkg_directory: PathBuf,
+
+        #[clap(long = "save-baseline")]
+        /// Save the section and function sizes of the package to this file
+        save_baseline: Option<PathBuf>,
+
+        #[clap(long = "compare")]
+        /// Compare the section and function sizes of the package to the baseline saved in this file
+        compare: Option<PathBuf>,
+
+        #[clap(long = "max-growth")]
+        /// Fail if the wasm grew by more than this compared to the baseline, either a size such as `10 KiB` or a percentage such as `2%`
+        max_growth: Option<MaxGrowth>,
+
+        #[clap(long = "top", default_value = "10")]
+        /// How many of the functions that grew or shrank the most to list
+        top: usize,
+
+        /// The path to the Rust crate. If not set, searches up the path from the current directory.
+        #[clap()]
+        path: Option<PathBuf>,
+    },
+
     #[clap(name = "new")]
     /// 🐑 create a new project with a template
     Generate {
@@ -110,6 +157,69 @@ pub enum Command {
     #[clap(name = "test")]
     /// 👩‍🔬  test your wasm!
     Test(TestOptions),
+
+    #[clap(name = "doctor")]
+    /// 🩺  check that everything wasm-pack needs is installed and working
+    Doctor {
+        /// The path to the Rust crate. If not set, searches up the path from the current directory.
+        #[clap()]
+        path: Option<PathBuf>,
+    },
+
+    #[clap(name = "fetch")]
+    /// ⬇️  download the tools that the crate needs, to build and test it offline
+    Fetch(FetchOptions),
+
+    #[clap(name = "cache", subcommand)]
+    /// 🗄️  list and remove the tools that wasm-pack has downloaded
+    Cache(CacheCommand),
+
+    #[clap(name = "config", subcommand)]
+    /// ⚙️  inspect wasm-pack's configuration
+    Config(ConfigCommand),
+
+    #[clap(name = "self", subcommand)]
+    /// 🔄  manage wasm-pack itself
+    SelfCommand(SelfCommand),
+}
+
+impl Command {
+    /// The path to the crate that the command works on, if one is given.
+    pub fn crate_path(&self) -> Option<PathBuf> {
+        match self {
+            Command::Build(opts) | Command::Watch(opts) => opts.path.clone(),
+            Command::Pack { path, .. }
+            | Command::Size { path, .. }
+            | Command::Publish { path, .. }
+            | Command::Doctor { path } => path.clone(),
+            Command::Test(opts) => opts.path(),
+            Command::Fetch(opts) => opts.path.clone(),
+            Command::Generate { .. }
+            | Command::Login { .. }
+            | Command::Cache(_)
+            | Command::Config(_)
+            | Command::SelfCommand(_) => None,
+        }
+    }
+
+    /// Whether the command reports invalid config files itself, rather than
+    /// failing before it runs.
+    pub fn diagnoses_config(&self) -> bool {
+        matches!(
+            self,
+            Command::Doctor { .. } | Command::Config(ConfigCommand::List)
+        )
+    }
+
+    /// The directory that the project's `wasm-pack.toml` is looked for from:
+    /// the crate that the command works on, or the current directory.
+    pub fn config_dir(&self) -> Result<PathBuf> {
+        let current_dir = std::env::current_dir()?;
+        Ok(match self.crate_path() {
+            Some(path) => current_dir.join(Path::new(&path)),
+            None => current_dir,
+        })
+    }
 }
 
 /// Run a command with the given logger!
@@ -119,7 +229,16 @@ pub fn run_wasm_pack(command: Command) -> Result<()> {
     match command {
         Command::Build(build_opts) => {
             info!("Running build command...");
-            Build::try_from_opts(build_opts).and_then(|mut b| b.run())
+            if build_opts.workspace || !build_opts.package.is_empty() {
+                Build::try_from_workspace_opts(build_opts)
+                    .and_then(|mut builds| Build::run_workspace(&mut builds))
+            } else {
+                Build::try_from_opts(build_opts).and_then(|mut b| b.run())
+            }
+        }
+        Command::Watch(build_opts) => {
+            info!("Running watch command...");
+            watch(build_opts)
         }
         Command::Pack {
             path,
@@ -129,6 +248,18 @@ pub fn run_wasm_pack(command: Command) -> Result<()> {
             info!("Path: {:?}", &path);
             pack(path, pkg_directory)
         }
+        Command::Size {
+            path,
+            pkg_directory,
+            save_baseline,
+            compare,
+            max_growth,
+            top,
+        } => {
+            info!("Running size command...");
+            info!("Path: {:?}", &path);
+            size(path, pkg_directory, save_baseline, compare, max_growth, top)
+        }
         Command::Generate {
             template,
             name,
@@ -166,5 +297,26 @@ pub fn run_wasm_pack(command: Command) -> Result<()> {
             info!("Running test command...");
             Test::try_from_opts(test_opts).and_then(|t| t.run())
         }
+        Command::Doctor { path } => {
+            info!("Running doctor command...");
+            info!("Path: {:?}", &path);
+            doctor(path)
+        }
+        Command::Fetch(fetch_opts) => {
+            info!("Running fetch command...");
+            fetch(fetch_opts)
+        }
+        Command::Cache(cache_command) => {
+            info!("Running cache command...");
+            cache(cache_command)
+        }
+        Command::Config(config_command) => {
+            info!("Running config command...");
+            config(config_command)
+        }
+        Command::SelfCommand(subcommand) => {
+            info!("Running self command...");
+            self_command(subcommand)
+        }
     }
 }
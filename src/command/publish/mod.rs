@@ -50,8 +50,8 @@ pub fn publish(
                 let target = Target::from_str(&target)?;
                 let build_opts = BuildOptions {
                     path: Some(crate_path.clone()),
-                    target,
-                    out_dir: out_dir.clone(),
+                    target: vec![target],
+                    out_dir: Some(out_dir.clone()),
                     ..Default::default()
                 };
                 Build::try_from_opts(build_opts)
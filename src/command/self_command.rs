@@ -0,0 +1,51 @@
+//! Implementation of the `wasm-pack self` commands.
+
+use crate::build;
+use crate::progressbar::Event;
+use crate::PBAR;
+use anyhow::{Context, Result};
+use clap::Subcommand;
+
+/// The `wasm-pack self` subcommands.
+#[derive(Debug, Subcommand)]
+pub enum SelfCommand {
+    /// 🔄  check whether a newer version of wasm-pack is available
+    #[clap(name = "check-update")]
+    CheckUpdate,
+}
+
+/// Run a `wasm-pack self` subcommand.
+pub fn self_command(command: SelfCommand) -> Result<()> {
+    match command {
+        SelfCommand::CheckUpdate => check_update(),
+    }
+}
+
+/// Look up the latest version of wasm-pack right away, even if the background
+/// check is disabled or looked it up recently, and report whether it is newer
+/// than this one.
+fn check_update() -> Result<()> {
+    let version =
+        build::fetch_wasm_pack_versions().context("failed to look up the latest wasm-pack")?;
+    let outdated = version.is_outdated();
+
+    if PBAR.is_json() {
+        PBAR.event(&Event::UpdateCheck {
+            local: &version.local,
+            latest: &version.latest,
+            outdated,
+        });
+    } else if outdated {
+        PBAR.warn(&format!(
+            "There's a newer version of wasm-pack available, the new version is: {}, you are using: {}. \
+             To update, navigate to: https://rustwasm.github.io/wasm-pack/installer/",
+            version.latest, version.local
+        ));
+    } else {
+        PBAR.info(&format!(
+            "wasm-pack {} is up to date, the latest version is: {}",
+            version.local, version.latest
+        ));
+    }
+    Ok(())
+}
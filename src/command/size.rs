@@ -0,0 +1,89 @@
+//! Implementation of the `wasm-pack size` command.
+
+use crate::command::utils::{find_pkg_directory, get_crate_path};
+use crate::emoji;
+use crate::size::{Baseline, MaxGrowth, SizeReport};
+use crate::PBAR;
+use anyhow::{anyhow, bail, Result};
+use log::info;
+use std::path::PathBuf;
+
+/// Report the size of the package in the `pkg` directory, and optionally save
+/// it as a baseline or compare it against one.
+pub fn size(
+    path: Option<PathBuf>,
+    pkg_directory: PathBuf,
+    save_baseline: Option<PathBuf>,
+    compare: Option<PathBuf>,
+    max_growth: Option<MaxGrowth>,
+    top: usize,
+) -> Result<()> {
+    if max_growth.is_some() && compare.is_none() {
+        bail!("`--max-growth` can only be used together with `--compare`");
+    }
+    let crate_path = get_crate_path(path)?;
+    let pkg_directory = find_pkg_directory(&crate_path, &pkg_directory).ok_or_else(|| {
+        anyhow!(
+            "Unable to find the pkg directory at path {:#?}, or in a child directory of {:#?}",
+            &crate_path,
+            &crate_path
+        )
+    })?;
+    info!("Measuring the package at {}...", pkg_directory.display());
+
+    let report = SizeReport::new(&pkg_directory)?;
+    PBAR.info(&format!(
+        "{}Size of {}:\n{}",
+        emoji::MEMO,
+        pkg_directory.display(),
+        report.table()
+    ));
+
+    let current = Baseline::new(&pkg_directory)?;
+    if current.modules.is_empty() {
+        bail!(
+            "There are no `.wasm` files in {}. Run `wasm-pack build` first.",
+            pkg_directory.display()
+        );
+    }
+    for module in &current.modules {
+        if module.functions.is_empty() {
+            PBAR.warn(&format!(
+                "{} has no `name` section, so function sizes can't be compared. \
+                 Keep it with `demangle-name-section = true` in the profile's `wasm-bindgen` \
+                 settings and `-g` in its `wasm-opt` arguments.",
+                module.path.display()
+            ));
+        }
+    }
+
+    if let Some(baseline_path) = &save_baseline {
+        current.save(baseline_path)?;
+        PBAR.info(&format!(
+            "Saved the size baseline to {}.",
+            baseline_path.display()
+        ));
+    }
+
+    if let Some(baseline_path) = &compare {
+        let baseline = Baseline::load(baseline_path)?;
+        let comparison = baseline.compare(&current);
+        PBAR.info(&format!(
+            "{}Size compared to {}:\n{}",
+            emoji::MEMO,
+            baseline_path.display(),
+            comparison.table(top)
+        ));
+        if let Some(max_growth) = max_growth {
+            if max_growth.is_exceeded_by(&comparison) {
+                bail!(
+                    "The wasm grew by {} bytes compared to {}, which is more than the allowed {}",
+                    comparison.delta(),
+                    baseline_path.display(),
+                    max_growth
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,10 @@
 //! Implementation of the `wasm-pack test` command.
 
 use crate::build;
+use crate::build::wasm_target::{is_custom_target, runner_env_var, DEFAULT_RUST_TARGET};
 use crate::cache;
-use crate::command::utils::get_crate_path;
+use crate::command::utils::{get_crate_path, run_step};
+use crate::config;
 use crate::install::{self, InstallMode, Tool};
 use crate::lockfile::Lockfile;
 use crate::manifest;
@@ -12,7 +14,7 @@ use binary_install::Cache;
 use clap::Args;
 use console::style;
 use log::info;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Instant;
 
@@ -73,6 +75,11 @@ pub struct TestOptions {
     /// Build with the release profile.
     pub release: bool,
 
+    #[clap(long = "rust-target", default_value = DEFAULT_RUST_TARGET)]
+    /// The target triple to compile the tests for, or the path to a custom
+    /// target JSON file.
+    pub rust_target: String,
+
     /// Path to the Rust crate, and extra options to pass to `cargo test`.
     ///
     /// If the path is not provided, this command searches up the path from the current directory.
@@ -82,6 +89,23 @@ pub struct TestOptions {
     pub path_and_extra_options: Vec<String>,
 }
 
+impl TestOptions {
+    /// The path to the crate, if one is given.
+    pub fn path(&self) -> Option<PathBuf> {
+        if first_arg_is_path(&self.path_and_extra_options) {
+            Some(PathBuf::from(&self.path_and_extra_options[0]))
+        } else {
+            None
+        }
+    }
+}
+
+fn first_arg_is_path(path_and_extra_options: &[String]) -> bool {
+    path_and_extra_options
+        .first()
+        .is_some_and(|first_arg| !first_arg.starts_with('-'))
+}
+
 /// A configured `wasm-pack test` command.
 pub struct Test {
     crate_path: PathBuf,
@@ -97,6 +121,7 @@ pub struct Test {
     safaridriver: Option<PathBuf>,
     headless: bool,
     release: bool,
+    rust_target: String,
     test_runner_path: Option<PathBuf>,
     extra_options: Vec<String>,
 }
@@ -111,6 +136,7 @@ impl Test {
             mode,
             headless,
             release,
+            rust_target,
             chrome,
             chromedriver,
             firefox,
@@ -120,12 +146,7 @@ impl Test {
             mut path_and_extra_options,
         } = test_opts;
 
-        let first_arg_is_path = path_and_extra_options
-            .get(0)
-            .map(|first_arg| !first_arg.starts_with("-"))
-            .unwrap_or(false);
-
-        let (path, extra_options) = if first_arg_is_path {
+        let (path, extra_options) = if first_arg_is_path(&path_and_extra_options) {
             let path = PathBuf::from_str(&path_and_extra_options.remove(0))?;
             let extra_options = path_and_extra_options;
 
@@ -149,6 +170,25 @@ impl Test {
             )
         }
 
+        let rust_target = if rust_target.is_empty() {
+            DEFAULT_RUST_TARGET.to_string()
+        } else if is_custom_target(&rust_target) && Path::new(&rust_target).is_relative() {
+            // Cargo runs inside the crate's directory.
+            std::env::current_dir()?
+                .join(&rust_target)
+                .to_string_lossy()
+                .into_owned()
+        } else {
+            rust_target
+        };
+
+        // Drivers set in wasm-pack's config are used for the browsers that
+        // are tested, but don't select browsers by themselves.
+        let config = config::get();
+        let chromedriver = chromedriver.or_else(|| Some(config.chromedriver()?.value));
+        let geckodriver = geckodriver.or_else(|| Some(config.geckodriver()?.value));
+        let safaridriver = safaridriver.or_else(|| Some(config.safaridriver()?.value));
+
         Ok(Test {
             cache: cache::get_wasm_pack_cache()?,
             crate_path,
@@ -163,6 +203,7 @@ impl Test {
             safaridriver,
             headless,
             release,
+            rust_target,
             test_runner_path: None,
             extra_options,
         })
@@ -178,8 +219,8 @@ impl Test {
         let process_steps = self.get_process_steps();
 
         let started = Instant::now();
-        for (_, process_step) in process_steps {
-            process_step(&mut self)?;
+        for (name, process_step) in process_steps {
+            run_step(name, || process_step(&mut self))?;
         }
         let duration = crate::command::utils::elapsed(started.elapsed());
         info!("Done in {}.", &duration);
@@ -251,7 +292,7 @@ impl Test {
 
     fn step_check_for_wasm_target(&mut self) -> Result<()> {
         info!("Adding wasm-target...");
-        build::wasm_target::check_for_wasm32_target()?;
+        build::wasm_target::check_for_wasm32_target(&self.rust_target)?;
         info!("Adding wasm-target was successful.");
         Ok(())
     }
@@ -267,7 +308,12 @@ impl Test {
             } else {
                 &self.extra_options
             };
-        build::cargo_build_wasm_tests(&self.crate_path, !self.release, extra_options)?;
+        build::cargo_build_wasm_tests(
+            &self.crate_path,
+            !self.release,
+            &self.rust_target,
+            extra_options,
+        )?;
 
         info!("Finished compiling tests to wasm.");
         Ok(())
@@ -292,6 +338,16 @@ impl Test {
             )
         }
 
+        // A test runner set in the config or by `WASM_PACK_TEST_RUNNER` is
+        // used without installing wasm-bindgen.
+        if let Some(path) =
+            install::configured_tool(&Tool::WasmBindgen, "test-runner", bindgen_version)?
+        {
+            install::report_tool("wasm-bindgen-test-runner", &path, Some(bindgen_version));
+            self.test_runner_path = Some(path);
+            return Ok(());
+        }
+
         let status = install::download_prebuilt_or_cargo_install(
             Tool::WasmBindgen,
             &self.cache,
@@ -301,6 +357,12 @@ impl Test {
 
         self.test_runner_path = match status {
             install::Status::Found(dl) => Some(dl.binary("wasm-bindgen-test-runner")?),
+            // Without `test-runner`, the runner is looked for next to the
+            // configured `wasm-bindgen`.
+            install::Status::Configured(path) => Some(path.with_file_name(format!(
+                "wasm-bindgen-test-runner{}",
+                std::env::consts::EXE_SUFFIX
+            ))),
             _ => bail!("Could not find 'wasm-bindgen-test-runner'."),
         };
 
@@ -314,12 +376,13 @@ impl Test {
         test::cargo_test_wasm(
             &self.crate_path,
             self.release,
+            &self.rust_target,
             vec![
                 (
-                    "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER",
-                    &**self.test_runner_path.as_ref().unwrap(),
+                    runner_env_var(&self.rust_target),
+                    self.test_runner_path.as_ref().unwrap().as_os_str(),
                 ),
-                ("WASM_BINDGEN_TEST_ONLY_NODE", "1".as_ref()),
+                ("WASM_BINDGEN_TEST_ONLY_NODE".to_string(), "1".as_ref()),
             ],
             &self.extra_options,
         )?;
@@ -330,10 +393,9 @@ impl Test {
     fn step_get_chromedriver(&mut self) -> Result<()> {
         assert!(self.chrome && self.chromedriver.is_none());
 
-        self.chromedriver = Some(webdriver::get_or_install_chromedriver(
-            &self.cache,
-            self.mode,
-        )?);
+        let chromedriver = webdriver::get_or_install_chromedriver(&self.cache, self.mode)?;
+        install::report_tool("chromedriver", &chromedriver, None);
+        self.chromedriver = Some(chromedriver);
         Ok(())
     }
 
@@ -346,19 +408,24 @@ impl Test {
         );
 
         let mut envs = self.webdriver_env();
-        envs.push(("CHROMEDRIVER", chromedriver));
+        envs.push(("CHROMEDRIVER".to_string(), chromedriver));
 
-        test::cargo_test_wasm(&self.crate_path, self.release, envs, &self.extra_options)?;
+        test::cargo_test_wasm(
+            &self.crate_path,
+            self.release,
+            &self.rust_target,
+            envs,
+            &self.extra_options,
+        )?;
         Ok(())
     }
 
     fn step_get_geckodriver(&mut self) -> Result<()> {
         assert!(self.firefox && self.geckodriver.is_none());
 
-        self.geckodriver = Some(webdriver::get_or_install_geckodriver(
-            &self.cache,
-            self.mode,
-        )?);
+        let geckodriver = webdriver::get_or_install_geckodriver(&self.cache, self.mode)?;
+        install::report_tool("geckodriver", &geckodriver, None);
+        self.geckodriver = Some(geckodriver);
         Ok(())
     }
 
@@ -371,16 +438,24 @@ impl Test {
         );
 
         let mut envs = self.webdriver_env();
-        envs.push(("GECKODRIVER", geckodriver));
+        envs.push(("GECKODRIVER".to_string(), geckodriver));
 
-        test::cargo_test_wasm(&self.crate_path, self.release, envs, &self.extra_options)?;
+        test::cargo_test_wasm(
+            &self.crate_path,
+            self.release,
+            &self.rust_target,
+            envs,
+            &self.extra_options,
+        )?;
         Ok(())
     }
 
     fn step_get_safaridriver(&mut self) -> Result<()> {
         assert!(self.safari && self.safaridriver.is_none());
 
-        self.safaridriver = Some(webdriver::get_safaridriver()?);
+        let safaridriver = webdriver::get_safaridriver()?;
+        install::report_tool("safaridriver", &safaridriver, None);
+        self.safaridriver = Some(safaridriver);
         Ok(())
     }
 
@@ -393,21 +468,27 @@ impl Test {
         );
 
         let mut envs = self.webdriver_env();
-        envs.push(("SAFARIDRIVER", safaridriver));
+        envs.push(("SAFARIDRIVER".to_string(), safaridriver));
 
-        test::cargo_test_wasm(&self.crate_path, self.release, envs, &self.extra_options)?;
+        test::cargo_test_wasm(
+            &self.crate_path,
+            self.release,
+            &self.rust_target,
+            envs,
+            &self.extra_options,
+        )?;
         Ok(())
     }
 
-    fn webdriver_env(&self) -> Vec<(&'static str, &str)> {
+    fn webdriver_env(&self) -> Vec<(String, &str)> {
         let test_runner = self.test_runner_path.as_ref().unwrap().to_str().unwrap();
         info!("Using wasm-bindgen test runner at {}", test_runner);
         let mut envs = vec![
-            ("CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER", test_runner),
-            ("WASM_BINDGEN_TEST_ONLY_WEB", "1"),
+            (runner_env_var(&self.rust_target), test_runner),
+            ("WASM_BINDGEN_TEST_ONLY_WEB".to_string(), "1"),
         ];
         if !self.headless {
-            envs.push(("NO_HEADLESS", "1"));
+            envs.push(("NO_HEADLESS".to_string(), "1"));
         }
         envs
     }
@@ -1,10 +1,12 @@
 //! Utility functions for commands.
 #![allow(clippy::redundant_closure)]
 
+use crate::progressbar::{self, Event};
+use crate::PBAR;
 use anyhow::Result;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
 /// If an explicit path is given, then use it, otherwise assume the current
@@ -70,3 +72,36 @@ pub fn elapsed(duration: Duration) -> String {
         format!("{}.{:02}s", secs, duration.subsec_nanos() / 10_000_000)
     }
 }
+
+/// Run the step called `name`, reporting when it starts and finishes as JSON
+/// events.
+pub fn run_step(name: &str, step: impl FnOnce() -> Result<()>) -> Result<()> {
+    PBAR.event(&Event::StepStarted { name });
+    let started = Instant::now();
+    let result = step();
+    PBAR.event(&Event::StepFinished {
+        name,
+        duration_ms: progressbar::duration_ms(started.elapsed()),
+        success: result.is_ok(),
+    });
+    result
+}
+
+/// Report every file in `out_dir` as a JSON event.
+pub fn report_output_files(out_dir: &Path) {
+    if !PBAR.is_json() {
+        return;
+    }
+    let files = WalkDir::new(out_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file());
+    for file in files {
+        let size = file.metadata().map_or(0, |metadata| metadata.len());
+        PBAR.event(&Event::OutputWritten {
+            path: file.path(),
+            size,
+        });
+    }
+}
@@ -0,0 +1,110 @@
+//! Implementation of the `wasm-pack watch` command.
+
+use crate::command::build::{Build, BuildOptions};
+use crate::command::utils::get_crate_path;
+use crate::emoji;
+use crate::progressbar::Event;
+use crate::watch::{self, Watcher};
+use crate::PBAR;
+use anyhow::{bail, Result};
+use log::info;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How long the sources have to stay unchanged before a rebuild starts.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Build the crate, then rebuild it every time its sources or the sources of
+/// its path dependencies change. Never returns unless the initial options are
+/// invalid.
+pub fn watch(build_opts: BuildOptions) -> Result<()> {
+    if build_opts.workspace || !build_opts.package.is_empty() {
+        bail!("`wasm-pack watch` does not support `--workspace` or `--package`");
+    }
+
+    let crate_path = match &build_opts.path {
+        Some(path) if !path.to_string_lossy().starts_with("--") => Some(path.clone()),
+        _ => None,
+    };
+    let mut watcher = Watcher::new(vec![get_crate_path(crate_path)?], Vec::new());
+    // The files that were copied to the output directory by the last
+    // successful build.
+    let mut synced = BTreeSet::new();
+
+    loop {
+        let snapshot = match Build::try_from_opts(build_opts.clone()) {
+            Ok(mut build) => {
+                let staging_dir = staging_dir(&build);
+                watcher = Watcher::new(
+                    build.crate_data.path_dependency_directories(),
+                    vec![
+                        build.crate_data.target_directory().to_path_buf(),
+                        build.out_dir.clone(),
+                        staging_dir.clone(),
+                    ],
+                );
+                // Take the snapshot before building, so that changes made
+                // during the build trigger another one.
+                let snapshot = watcher.snapshot();
+                if let Err(e) = rebuild(&mut build, staging_dir, &mut synced) {
+                    PBAR.error(&format!("{:#}", e));
+                }
+                snapshot
+            }
+            Err(e) => {
+                PBAR.error(&format!("{:#}", e));
+                watcher.snapshot()
+            }
+        };
+
+        info!("Watching {:?} for changes...", watcher.roots());
+        PBAR.info(&format!("{}Watching for changes...", emoji::SPARKLE));
+        watcher.wait_for_change(&snapshot, DEBOUNCE);
+    }
+}
+
+/// The directory that each build is generated into, before the files that
+/// changed are copied to the real output directory.
+fn staging_dir(build: &Build) -> PathBuf {
+    build
+        .crate_data
+        .target_directory()
+        .join("wasm-pack-watch")
+        .join(build.crate_data.package_name())
+}
+
+fn rebuild(build: &mut Build, staging_dir: PathBuf, synced: &mut BTreeSet<PathBuf>) -> Result<()> {
+    let started = Instant::now();
+    let out_dir = std::mem::replace(&mut build.out_dir, staging_dir.clone());
+
+    // Remove the outputs of the last build, so that the ones which are no
+    // longer generated get removed from the output directory too, but keep
+    // the bindings that can be reused if the wasm hasn't changed.
+    watch::clean_staging_dir(&staging_dir)?;
+    build.run_process_steps()?;
+
+    let (now_synced, changed) = watch::sync_dir(&staging_dir, &out_dir, synced)?;
+    *synced = now_synced;
+    for file in &changed {
+        let path = out_dir.join(file);
+        info!("Updated {}", path.display());
+        if let Ok(metadata) = fs::metadata(&path) {
+            PBAR.event(&Event::OutputWritten {
+                path: &path,
+                size: metadata.len(),
+            });
+        }
+    }
+
+    let duration = crate::command::utils::elapsed(started.elapsed());
+    PBAR.info(&format!(
+        "{} Rebuilt in {}, {} file(s) updated in {}.",
+        emoji::PACKAGE,
+        duration,
+        changed.len(),
+        out_dir.display()
+    ));
+    Ok(())
+}
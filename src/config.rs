@@ -0,0 +1,555 @@
+//! wasm-pack's own settings, which don't belong in a crate's `Cargo.toml`.
+//!
+//! Settings are read from a `wasm-pack.toml` file in the current directory or
+//! any of its parents, which is usually checked in at the root of a
+//! repository, and from the user's `~/.config/wasm-pack/config.toml`. The
+//! project's file takes precedence over the user's, and environment variables
+//! take precedence over both.
+
+use crate::cache::Age;
+use crate::progressbar::LogLevel;
+use crate::PBAR;
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// The name of the project's config file.
+pub const PROJECT_CONFIG_FILE: &str = "wasm-pack.toml";
+
+const MIRROR_ENV_PREFIX: &str = "WASM_PACK_MIRROR_";
+
+/// The environment variable that disables the update check when it is set to
+/// anything but an empty string, `0` or `false`.
+pub const NO_UPDATE_CHECK_ENV: &str = "WASM_PACK_NO_UPDATE_CHECK";
+
+/// The environment variable that sets the endpoint of the update check.
+pub const UPDATE_CHECK_URL_ENV: &str = "WASM_PACK_UPDATE_CHECK_URL";
+
+/// Where the latest version of wasm-pack is looked up by default.
+pub const DEFAULT_UPDATE_CHECK_URL: &str = "https://crates.io/api/v1/crates/wasm-pack";
+
+/// How long the latest version of wasm-pack is used for by default before it
+/// is looked up again.
+const DEFAULT_UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The environment variable that sets the mirror for `tool`, such as
+/// `WASM_PACK_MIRROR_WASM_BINDGEN`.
+pub fn mirror_env_var(tool: &str) -> String {
+    format!(
+        "{}{}",
+        MIRROR_ENV_PREFIX,
+        tool.to_uppercase().replace('-', "_")
+    )
+}
+
+/// The tools whose binary can be set in the config, or with a
+/// `WASM_PACK_<TOOL>` environment variable.
+pub const TOOLS: &[&str] = &["wasm-bindgen", "wasm-opt", "test-runner", "cargo-generate"];
+
+/// The environment variable that sets the binary of `tool`, such as
+/// `WASM_PACK_WASM_BINDGEN`.
+pub fn tool_env_var(tool: &str) -> String {
+    format!("WASM_PACK_{}", tool.to_uppercase().replace('-', "_"))
+}
+
+/// The contents of one config file.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ConfigFile {
+    /// Where downloaded and installed tools are cached.
+    pub cache_dir: Option<PathBuf>,
+    /// The default `--log-level`.
+    pub log_level: Option<String>,
+    /// The npm client used by `pack`, `publish` and `login`.
+    pub npm_client: Option<String>,
+    /// Whether to check for newer versions of wasm-pack in the background.
+    pub update_check: Option<bool>,
+    /// The crates.io API endpoint that the latest version of wasm-pack is
+    /// looked up at.
+    pub update_check_url: Option<String>,
+    /// How long the latest version that was looked up is used for before it
+    /// is looked up again, such as `24h` or `7d`.
+    pub update_check_interval: Option<String>,
+    /// The `chromedriver` used for `wasm-pack test --chrome`.
+    pub chromedriver: Option<PathBuf>,
+    /// The `geckodriver` used for `wasm-pack test --firefox`.
+    pub geckodriver: Option<PathBuf>,
+    /// The `safaridriver` used for `wasm-pack test --safari`.
+    pub safaridriver: Option<PathBuf>,
+    /// The `wasm-bindgen` to run instead of finding or installing one.
+    pub wasm_bindgen: Option<PathBuf>,
+    /// The `wasm-opt` to run instead of finding or installing one.
+    pub wasm_opt: Option<PathBuf>,
+    /// The `wasm-bindgen-test-runner` used by `wasm-pack test`.
+    pub test_runner: Option<PathBuf>,
+    /// The `cargo-generate` used by `wasm-pack new`.
+    pub cargo_generate: Option<PathBuf>,
+    /// URL templates to download prebuilt tools from instead of their
+    /// official releases, by tool name.
+    #[serde(default)]
+    pub mirrors: BTreeMap<String, String>,
+    /// The SHA-256 digests that downloaded archives must have, by file name.
+    #[serde(default)]
+    pub checksums: BTreeMap<String, String>,
+}
+
+/// Where the effective value of a setting comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// wasm-pack's built-in default.
+    Default,
+    /// An environment variable.
+    Env(String),
+    /// A config file.
+    File(PathBuf),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::Env(var) => write!(f, "environment variable {}", var),
+            Source::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl serde::Serialize for Source {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A setting listed by `wasm-pack config list`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ListedSetting {
+    /// The key of the setting, as it is written in a config file.
+    pub key: String,
+    /// The value, formatted as TOML.
+    pub value: String,
+    /// Where the value comes from.
+    pub source: Source,
+}
+
+/// The effective value of a setting, and where it comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Setting<T> {
+    /// The value.
+    pub value: T,
+    /// Where the value comes from.
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    fn default(value: T) -> Setting<T> {
+        Setting {
+            value,
+            source: Source::Default,
+        }
+    }
+}
+
+/// All of the config files that apply, in order of precedence.
+#[derive(Debug, Default)]
+pub struct Config {
+    files: Vec<(PathBuf, ConfigFile)>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Why the config files couldn't be loaded by `init_or_default`.
+static LOAD_ERROR: OnceLock<anyhow::Error> = OnceLock::new();
+
+/// Load the config files that apply to `dir`, the directory of the crate that
+/// the command works on, returning an error if any of them is invalid. Does
+/// nothing if they were loaded already.
+pub fn init(dir: &Path) -> Result<&'static Config> {
+    if let Some(config) = CONFIG.get() {
+        return Ok(config);
+    }
+    let config = Config::load_from(dir, Config::user_config_path())?;
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// Like `init`, but if a config file is invalid, the defaults are used and the
+/// error is kept for [`load_error`] to report, so that commands which diagnose
+/// the config can still run.
+pub fn init_or_default(dir: &Path) -> &'static Config {
+    CONFIG.get_or_init(
+        || match Config::load_from(dir, Config::user_config_path()) {
+            Ok(config) => config,
+            Err(e) => {
+                drop(LOAD_ERROR.set(e));
+                Config::default()
+            }
+        },
+    )
+}
+
+/// Why the config files couldn't be loaded by `init_or_default`, if they
+/// couldn't.
+pub fn load_error() -> Option<&'static anyhow::Error> {
+    LOAD_ERROR.get()
+}
+
+/// The config that applies to the current directory. Invalid config files are
+/// reported as a warning and ignored.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| {
+        Config::load().unwrap_or_else(|e| {
+            PBAR.warn(&format!("{:#}", e));
+            Config::default()
+        })
+    })
+}
+
+impl Config {
+    /// Read the project's config file found from the current directory, and
+    /// the user's config file.
+    pub fn load() -> Result<Config> {
+        Config::load_from(&env::current_dir()?, Config::user_config_path())
+    }
+
+    /// Read the project's config file found from `dir`, and `user_config` if
+    /// it is given.
+    pub fn load_from(dir: &Path, user_config: Option<PathBuf>) -> Result<Config> {
+        let project_config = dir
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file());
+        let mut files = Vec::new();
+        for path in project_config.into_iter().chain(user_config) {
+            if path.is_file() {
+                let file = Config::read(&path)?;
+                files.push((path, file));
+            }
+        }
+        Ok(Config { files })
+    }
+
+    /// The config files that were read, in order of precedence.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// The user's config file: `$XDG_CONFIG_HOME/wasm-pack/config.toml`, which
+    /// defaults to `~/.config/wasm-pack/config.toml`.
+    pub fn user_config_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME")
+                    .or_else(|| env::var_os("USERPROFILE"))
+                    .map(|home| Path::new(&home).join(".config"))
+            })?;
+        Some(config_home.join("wasm-pack").join("config.toml"))
+    }
+
+    fn read(path: &Path) -> Result<ConfigFile> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut file: ConfigFile =
+            serde_ignored::deserialize(toml::Deserializer::new(&contents), |key| {
+                PBAR.warn(&format!(
+                    "\"{}\" is an unknown key and will be ignored. Please check {}.",
+                    key,
+                    path.display()
+                ));
+            })
+            .with_context(|| anyhow!("failed to parse {}", path.display()))?;
+
+        if let Some(log_level) = &file.log_level {
+            if let Err(e) = log_level.parse::<LogLevel>() {
+                bail!("{} in {}", e, path.display());
+            }
+        }
+
+        if let Some(interval) = &file.update_check_interval {
+            if let Err(e) = interval.parse::<Age>() {
+                bail!("{} for update-check-interval in {}", e, path.display());
+            }
+        }
+
+        for (archive, digest) in &mut file.checksums {
+            *digest = digest.to_ascii_lowercase();
+            if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                bail!(
+                    "The checksum of {} in {} is not a SHA-256 digest of 64 hexadecimal digits",
+                    archive,
+                    path.display()
+                );
+            }
+        }
+
+        // Relative paths are relative to the directory of the config file.
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        for path in [
+            &mut file.cache_dir,
+            &mut file.chromedriver,
+            &mut file.geckodriver,
+            &mut file.safaridriver,
+            &mut file.wasm_bindgen,
+            &mut file.wasm_opt,
+            &mut file.test_runner,
+            &mut file.cargo_generate,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = dir.join(&*path);
+        }
+        Ok(file)
+    }
+
+    /// The value of the first config file that sets it.
+    fn lookup<T>(&self, get: impl Fn(&ConfigFile) -> Option<T>) -> Option<Setting<T>> {
+        self.files.iter().find_map(|(path, file)| {
+            get(file).map(|value| Setting {
+                value,
+                source: Source::File(path.clone()),
+            })
+        })
+    }
+
+    /// The directory that tools are cached in, if it isn't the default one.
+    pub fn cache_dir(&self) -> Option<Setting<PathBuf>> {
+        if let Some(path) = env::var_os("WASM_PACK_CACHE") {
+            return Some(Setting {
+                value: PathBuf::from(path),
+                source: Source::Env("WASM_PACK_CACHE".to_string()),
+            });
+        }
+        self.lookup(|file| file.cache_dir.clone())
+    }
+
+    /// The log level to use when `--log-level` isn't given.
+    pub fn log_level(&self) -> Setting<LogLevel> {
+        self.lookup(|file| file.log_level.as_ref()?.parse().ok())
+            .unwrap_or_else(|| Setting::default(LogLevel::Info))
+    }
+
+    /// The npm client to run, `npm` by default.
+    pub fn npm_client(&self) -> Setting<String> {
+        self.lookup(|file| file.npm_client.clone())
+            .unwrap_or_else(|| Setting::default("npm".to_string()))
+    }
+
+    /// Whether to check for newer versions of wasm-pack. Setting
+    /// `WASM_PACK_NO_UPDATE_CHECK` disables the check regardless of the config
+    /// files.
+    pub fn update_check(&self) -> Setting<bool> {
+        if let Ok(value) = env::var(NO_UPDATE_CHECK_ENV) {
+            if !matches!(value.as_str(), "" | "0" | "false") {
+                return Setting {
+                    value: false,
+                    source: Source::Env(NO_UPDATE_CHECK_ENV.to_string()),
+                };
+            }
+        }
+        self.lookup(|file| file.update_check)
+            .unwrap_or_else(|| Setting::default(true))
+    }
+
+    /// The endpoint that the latest version of wasm-pack is looked up at. The
+    /// `WASM_PACK_UPDATE_CHECK_URL` environment variable takes precedence over
+    /// config files.
+    pub fn update_check_url(&self) -> Setting<String> {
+        if let Some(url) = env::var(UPDATE_CHECK_URL_ENV)
+            .ok()
+            .filter(|url| !url.is_empty())
+        {
+            return Setting {
+                value: url,
+                source: Source::Env(UPDATE_CHECK_URL_ENV.to_string()),
+            };
+        }
+        self.lookup(|file| file.update_check_url.clone())
+            .unwrap_or_else(|| Setting::default(DEFAULT_UPDATE_CHECK_URL.to_string()))
+    }
+
+    /// How long the latest version of wasm-pack that was looked up is used
+    /// for, 24 hours by default.
+    pub fn update_check_interval(&self) -> Setting<Duration> {
+        self.lookup(|file| {
+            let Age(interval) = file.update_check_interval.as_ref()?.parse().ok()?;
+            Some(interval)
+        })
+        .unwrap_or_else(|| Setting::default(DEFAULT_UPDATE_CHECK_INTERVAL))
+    }
+
+    /// The configured `chromedriver`, if any.
+    pub fn chromedriver(&self) -> Option<Setting<PathBuf>> {
+        self.lookup(|file| file.chromedriver.clone())
+    }
+
+    /// The configured `geckodriver`, if any.
+    pub fn geckodriver(&self) -> Option<Setting<PathBuf>> {
+        self.lookup(|file| file.geckodriver.clone())
+    }
+
+    /// The configured `safaridriver`, if any.
+    pub fn safaridriver(&self) -> Option<Setting<PathBuf>> {
+        self.lookup(|file| file.safaridriver.clone())
+    }
+
+    /// The binary to run for `tool`, one of [`TOOLS`], instead of finding or
+    /// installing it, if one is configured. The `WASM_PACK_<TOOL>` environment
+    /// variable, such as `WASM_PACK_WASM_BINDGEN`, takes precedence over
+    /// config files.
+    pub fn tool(&self, tool: &str) -> Option<Setting<PathBuf>> {
+        let var = tool_env_var(tool);
+        if let Some(path) = env::var_os(&var).filter(|path| !path.is_empty()) {
+            return Some(Setting {
+                value: PathBuf::from(path),
+                source: Source::Env(var),
+            });
+        }
+        self.lookup(|file| match tool {
+            "wasm-bindgen" => file.wasm_bindgen.clone(),
+            "wasm-opt" => file.wasm_opt.clone(),
+            "test-runner" => file.test_runner.clone(),
+            "cargo-generate" => file.cargo_generate.clone(),
+            _ => None,
+        })
+    }
+
+    /// The URL template to download `tool` from, if one is configured. The
+    /// `WASM_PACK_MIRROR_<TOOL>` environment variable, such as
+    /// `WASM_PACK_MIRROR_WASM_BINDGEN`, takes precedence over config files.
+    pub fn mirror(&self, tool: &str) -> Option<Setting<String>> {
+        let var = mirror_env_var(tool);
+        if let Some(template) = env::var(&var).ok().filter(|template| !template.is_empty()) {
+            return Some(Setting {
+                value: template,
+                source: Source::Env(var),
+            });
+        }
+        self.lookup(|file| file.mirrors.get(tool).cloned())
+    }
+
+    /// The URL to download `version` of `tool` for `target` from, if a mirror
+    /// is configured for it.
+    pub fn mirror_url(&self, tool: &str, version: &str, target: &str) -> Option<String> {
+        let mirror = self.mirror(tool)?;
+        let url = mirror
+            .value
+            .replace("{version}", version)
+            .replace("{target}", target)
+            .replace("{tool}", tool);
+        log::info!("Using the {} mirror from {}: {}", tool, mirror.source, url);
+        Some(url)
+    }
+
+    /// The SHA-256 digest pinned for the archive called `file_name`, if any.
+    pub fn checksum(&self, file_name: &str) -> Option<Setting<String>> {
+        self.lookup(|file| file.checksums.get(file_name).cloned())
+    }
+
+    /// Every setting that has a value.
+    pub fn list(&self) -> Vec<ListedSetting> {
+        fn entry<T>(
+            key: &str,
+            setting: Setting<T>,
+            format: impl Fn(&T) -> String,
+        ) -> ListedSetting {
+            ListedSetting {
+                key: key.to_string(),
+                value: format(&setting.value),
+                source: setting.source,
+            }
+        }
+        let string = |value: &String| format!("{:?}", value);
+        let path = |value: &PathBuf| format!("{:?}", value.display().to_string());
+
+        let mut list = Vec::new();
+        list.extend(
+            self.cache_dir()
+                .map(|setting| entry("cache-dir", setting, path)),
+        );
+        list.push(entry("log-level", self.log_level(), |level| {
+            format!("{:?}", format!("{:?}", level).to_lowercase())
+        }));
+        list.push(entry("npm-client", self.npm_client(), string));
+        list.push(entry("update-check", self.update_check(), bool::to_string));
+        list.push(entry("update-check-url", self.update_check_url(), string));
+        list.push(entry(
+            "update-check-interval",
+            self.update_check_interval(),
+            |interval| format!("{:?}", format_interval(*interval)),
+        ));
+        list.extend(
+            self.chromedriver()
+                .map(|setting| entry("chromedriver", setting, path)),
+        );
+        list.extend(
+            self.geckodriver()
+                .map(|setting| entry("geckodriver", setting, path)),
+        );
+        list.extend(
+            self.safaridriver()
+                .map(|setting| entry("safaridriver", setting, path)),
+        );
+        for tool in TOOLS {
+            list.extend(self.tool(tool).map(|setting| entry(tool, setting, path)));
+        }
+
+        let env_tools: Vec<String> = env::vars()
+            .filter_map(|(var, _)| {
+                let tool = var.strip_prefix(MIRROR_ENV_PREFIX)?;
+                Some(tool.to_lowercase().replace('_', "-"))
+            })
+            .collect();
+        let mut tools: Vec<&String> = self
+            .files
+            .iter()
+            .flat_map(|(_, file)| file.mirrors.keys())
+            .chain(&env_tools)
+            .collect();
+        tools.sort();
+        tools.dedup();
+        for tool in tools {
+            if let Some(setting) = self.mirror(tool) {
+                list.push(entry(&format!("mirrors.{}", tool), setting, string));
+            }
+        }
+
+        let mut archives: Vec<&String> = self
+            .files
+            .iter()
+            .flat_map(|(_, file)| file.checksums.keys())
+            .collect();
+        archives.sort();
+        archives.dedup();
+        for archive in archives {
+            if let Some(setting) = self.checksum(archive) {
+                list.push(entry(&format!("checksums.{:?}", archive), setting, string));
+            }
+        }
+        list
+    }
+}
+
+/// Format `interval` the way `update-check-interval` is written, in the
+/// largest unit that it is a whole number of.
+fn format_interval(interval: Duration) -> String {
+    let seconds = interval.as_secs();
+    let units = [
+        ("w", 7 * 24 * 60 * 60),
+        ("d", 24 * 60 * 60),
+        ("h", 60 * 60),
+        ("m", 60),
+    ];
+    match units
+        .iter()
+        .find(|(_, unit)| seconds != 0 && seconds.checked_rem(*unit) == Some(0))
+    {
+        Some((suffix, unit)) => format!("{}{}", seconds / unit, suffix),
+        None => format!("{}s", seconds),
+    }
+}
@@ -0,0 +1,420 @@
+//! Diagnosing the environment that wasm-pack runs in, for `wasm-pack doctor`.
+
+use crate::build::{self, wasm_target};
+use crate::config;
+use crate::install::{self, Tool};
+use crate::lockfile::Lockfile;
+use crate::manifest::CrateData;
+use crate::target;
+use crate::wasm_opt;
+use binary_install::Cache;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    /// Everything is fine.
+    Pass,
+    /// Some commands or options won't work, or will be slower than they need
+    /// to be.
+    Warn,
+    /// Building won't work.
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The result of checking one part of the environment.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    /// What was checked.
+    pub name: String,
+    /// The outcome.
+    pub status: Status,
+    /// What was found.
+    pub message: String,
+    /// How to fix a warning or failure.
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, message: impl Into<String>) -> Check {
+        Check {
+            name: name.to_string(),
+            status: Status::Pass,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &str, message: impl Into<String>, fix: impl Into<String>) -> Check {
+        Check {
+            name: name.to_string(),
+            status: Status::Warn,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &str, message: impl Into<String>, fix: impl Into<String>) -> Check {
+        Check {
+            name: name.to_string(),
+            status: Status::Fail,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Check the toolchain, the tools wasm-pack downloads and the tools it runs,
+/// for the crate at `crate_path` if there is one. Nothing is installed.
+pub fn checks(crate_path: Option<&Path>, cache: &Cache) -> Vec<Check> {
+    let crate_data = crate_path.map(|path| CrateData::new(path, None));
+    let rust_target = match &crate_data {
+        Some(Ok(data)) => data.build_defaults().rust_target.clone(),
+        _ => None,
+    }
+    .unwrap_or_else(|| wasm_target::DEFAULT_RUST_TARGET.to_string());
+
+    let wasm_opt_version = match &crate_data {
+        Some(Ok(data)) => data.build_defaults().wasm_opt_version.clone(),
+        _ => None,
+    };
+
+    let mut checks = vec![
+        check_config(),
+        check_rustc(),
+        check_rustup(),
+        check_target(&rust_target),
+    ];
+    match crate_data {
+        Some(Ok(data)) => checks.push(check_wasm_bindgen(&data, cache)),
+        Some(Err(e)) => checks.push(Check::fail(
+            "wasm-bindgen",
+            format!("Couldn't read the crate: {:#}", e),
+            "Run `cargo metadata` in the crate to see what is wrong with its Cargo.toml.",
+        )),
+        None => checks.push(Check::warn(
+            "wasm-bindgen",
+            "Not in a crate, so the wasm-bindgen version couldn't be checked.",
+            "Run `wasm-pack doctor` from the crate, or pass the path to it.",
+        )),
+    }
+    checks.push(check_wasm_opt(wasm_opt_version.as_deref(), cache));
+    checks.push(check_node());
+    checks.push(check_npm());
+    checks.push(check_driver(
+        "chromedriver",
+        config::get().chromedriver().map(|setting| setting.value),
+        "`wasm-pack test --chrome` will download it.",
+    ));
+    checks.push(check_driver(
+        "geckodriver",
+        config::get().geckodriver().map(|setting| setting.value),
+        "`wasm-pack test --firefox` will download it.",
+    ));
+    if target::MACOS {
+        checks.push(check_driver(
+            "safaridriver",
+            config::get().safaridriver().map(|setting| setting.value),
+            "Enable it with `safaridriver --enable`, which comes with Safari.",
+        ));
+    }
+    checks
+}
+
+fn check_config() -> Check {
+    let name = "config";
+    if let Some(e) = config::load_error() {
+        return Check::fail(
+            name,
+            format!("{:#}", e),
+            "Fix or remove the config file. Until then, every other command fails.",
+        );
+    }
+    let files: Vec<String> = config::get()
+        .files()
+        .map(|path| path.display().to_string())
+        .collect();
+    if files.is_empty() {
+        Check::pass(name, "no config files, so the defaults are used")
+    } else {
+        Check::pass(name, format!("read {}", files.join(", ")))
+    }
+}
+
+fn check_rustc() -> Check {
+    match build::check_rustc_version() {
+        Ok(_) => Check::pass(
+            "rustc",
+            version_of("rustc").unwrap_or_else(|| "rustc".to_string()),
+        ),
+        Err(e) => Check::fail(
+            "rustc",
+            e.to_string(),
+            "Install Rust with rustup: https://rustup.rs",
+        ),
+    }
+}
+
+fn check_rustup() -> Check {
+    let sysroot = match wasm_target::get_rustc_sysroot() {
+        Ok(sysroot) => sysroot,
+        Err(e) => {
+            return Check::fail(
+                "rustup",
+                format!("Couldn't find rustc's sysroot: {:#}", e),
+                "Check that `rustc --print sysroot` works.",
+            )
+        }
+    };
+    if !sysroot.to_string_lossy().contains("rustup") {
+        return Check::warn(
+            "rustup",
+            format!(
+                "rustc isn't managed by rustup (sysroot {}), so wasm-pack can't add targets \
+                 or components.",
+                sysroot.display()
+            ),
+            "Install targets manually, see \
+             https://rustwasm.github.io/wasm-pack/book/prerequisites/non-rustup-setups.html",
+        );
+    }
+    match version_of("rustup") {
+        Some(version) => Check::pass(
+            "rustup",
+            format!("{} (sysroot {})", version, sysroot.display()),
+        ),
+        None => Check::warn(
+            "rustup",
+            format!(
+                "rustc is managed by rustup (sysroot {}), but `rustup` isn't on the PATH.",
+                sysroot.display()
+            ),
+            "Add rustup's bin directory, usually ~/.cargo/bin, to the PATH.",
+        ),
+    }
+}
+
+fn check_target(rust_target: &str) -> Check {
+    if wasm_target::is_target_installed(rust_target) {
+        Check::pass("target", format!("{} is installed", rust_target))
+    } else {
+        Check::fail(
+            "target",
+            format!("{} is not installed", rust_target),
+            format!("Run `rustup target add {}`.", rust_target),
+        )
+    }
+}
+
+fn check_wasm_bindgen(data: &CrateData, cache: &Cache) -> Check {
+    let name = "wasm-bindgen";
+    let lockfile = match Lockfile::new(data) {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            return Check::warn(
+                name,
+                format!("{:#}", e),
+                "Run `cargo generate-lockfile` in the crate.",
+            )
+        }
+    };
+    let version = match lockfile.wasm_bindgen_version() {
+        Some(version) => version,
+        None => {
+            return Check::fail(
+                name,
+                "The crate doesn't depend on wasm-bindgen.",
+                "Add `wasm-bindgen = \"0.2\"` to the crate's [dependencies].",
+            )
+        }
+    };
+
+    match install::configured_tool(&Tool::WasmBindgen, name, version) {
+        Ok(Some(path)) => {
+            return Check::pass(
+                name,
+                format!("{} at {} matches Cargo.lock", version, path.display()),
+            )
+        }
+        Ok(None) => {}
+        Err(e) => return configured_tool_failed(name, e),
+    }
+
+    let on_path = which::which(name)
+        .ok()
+        .map(|path| (install::get_cli_version(&Tool::WasmBindgen, &path), path));
+    match &on_path {
+        Some((Ok(cli_version), path)) if cli_version == version => {
+            return Check::pass(
+                name,
+                format!("{} at {} matches Cargo.lock", version, path.display()),
+            )
+        }
+        _ => {}
+    }
+    if let Ok(install::Status::Found(download)) =
+        install::download_prebuilt(&Tool::WasmBindgen, cache, version, false)
+    {
+        if let Ok(path) = download.binary(name) {
+            return Check::pass(name, format!("{} is cached at {}", version, path.display()));
+        }
+    }
+
+    let fix = format!(
+        "`wasm-pack build` will download it, or run `cargo install wasm-bindgen-cli --version {}`.",
+        version
+    );
+    match on_path {
+        Some((Ok(cli_version), path)) => Check::warn(
+            name,
+            format!(
+                "Cargo.lock has {}, but the wasm-bindgen at {} is {}.",
+                version,
+                path.display(),
+                cli_version
+            ),
+            fix,
+        ),
+        _ => Check::warn(
+            name,
+            format!("Cargo.lock has {}, which isn't installed.", version),
+            fix,
+        ),
+    }
+}
+
+fn check_wasm_opt(pinned: Option<&str>, cache: &Cache) -> Check {
+    let name = "wasm-opt";
+    match install::configured_tool(&Tool::WasmOpt, name, pinned.unwrap_or("latest")) {
+        Ok(Some(path)) => return Check::pass(name, format!("configured at {}", path.display())),
+        Ok(None) => {}
+        Err(e) => return configured_tool_failed(name, e),
+    }
+    let on_path = which::which(name).ok();
+    match (&on_path, pinned) {
+        (Some(path), None) => {
+            let version = version_of(&path.to_string_lossy()).unwrap_or_default();
+            return Check::pass(name, format!("{} at {}", version, path.display()));
+        }
+        (Some(path), Some(pinned)) => {
+            if install::get_cli_version(&Tool::WasmOpt, path).is_ok_and(|v| v == pinned) {
+                return Check::pass(
+                    name,
+                    format!("{} at {} matches wasm-opt-version", pinned, path.display()),
+                );
+            }
+        }
+        (None, _) => {}
+    }
+    let version = pinned.unwrap_or("latest");
+    match wasm_opt::install(cache, version, false) {
+        Ok(install::Status::Found(download)) => {
+            Check::pass(name, format!("cached at {}", download.path().display()))
+        }
+        Ok(_) => match (on_path, pinned) {
+            (Some(path), Some(pinned)) => Check::warn(
+                name,
+                format!(
+                    "wasm-opt-version is {}, but the wasm-opt at {} isn't that version.",
+                    pinned,
+                    path.display()
+                ),
+                "`wasm-pack build` will download it.",
+            ),
+            _ => Check::warn(
+                name,
+                "wasm-opt isn't installed.",
+                "`wasm-pack build` will download it, or install binaryen.",
+            ),
+        },
+        Err(e) => Check::warn(
+            name,
+            format!("{:#}", e),
+            "Install binaryen, or skip optimizing with `wasm-opt = false` in \
+             [package.metadata.wasm-pack.profile.release].",
+        ),
+    }
+}
+
+fn configured_tool_failed(name: &str, e: anyhow::Error) -> Check {
+    Check::fail(
+        name,
+        format!("{:#}", e),
+        format!(
+            "Fix the `{}` setting in `wasm-pack config list`, or unset {}.",
+            name,
+            config::tool_env_var(name)
+        ),
+    )
+}
+
+fn check_node() -> Check {
+    match version_of("node") {
+        Some(version) => Check::pass("node", version),
+        None => Check::warn(
+            "node",
+            "node isn't on the PATH, so `wasm-pack test --node` won't work.",
+            "Install Node.js: https://nodejs.org",
+        ),
+    }
+}
+
+fn check_npm() -> Check {
+    let npm_client = config::get().npm_client().value;
+    match version_of(&npm_client) {
+        Some(version) => Check::pass("npm", format!("{} {}", npm_client, version)),
+        None => Check::warn(
+            "npm",
+            format!(
+                "{} isn't on the PATH, so `wasm-pack pack`, `publish` and `login` won't work.",
+                npm_client
+            ),
+            "Install npm, which comes with Node.js: https://nodejs.org",
+        ),
+    }
+}
+
+fn check_driver(name: &str, configured: Option<PathBuf>, fix: &str) -> Check {
+    let path = match configured {
+        Some(path) if path.is_file() => path,
+        Some(path) => {
+            return Check::fail(
+                name,
+                format!("The configured {} doesn't exist: {}", name, path.display()),
+                format!("Fix the `{}` setting in `wasm-pack config list`.", name),
+            )
+        }
+        None => match which::which(name) {
+            Ok(path) => path,
+            Err(_) => {
+                return Check::warn(name, format!("{} isn't on the PATH.", name), fix);
+            }
+        },
+    };
+    let version = version_of(&path.to_string_lossy()).unwrap_or_default();
+    Check::pass(name, format!("{} at {}", version, path.display()))
+}
+
+/// The first line that `program --version` prints, if it can be run.
+fn version_of(program: &str) -> Option<String> {
+    let output = Command::new(program).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
@@ -0,0 +1,149 @@
+//! Fingerprints of the inputs of `wasm-bindgen` and `wasm-opt`, so that they
+//! can be skipped when the compiled wasm has not changed since the last build.
+
+use crate::command::build::Target;
+use anyhow::{Context, Result};
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+/// The file in the output directory that the fingerprints are stored in.
+pub const FINGERPRINT_FILE: &str = ".wasm-pack-fingerprint.json";
+
+/// Everything that determines the output of `wasm-bindgen` and `wasm-opt`
+/// for one target.
+#[derive(Debug)]
+pub struct Inputs<'a> {
+    /// The `.wasm` file compiled by cargo.
+    pub wasm: &'a Path,
+    /// The version of the `wasm-bindgen` CLI.
+    pub wasm_bindgen_version: &'a str,
+    /// The flags passed to `wasm-bindgen`.
+    pub wasm_bindgen_flags: &'a [String],
+    /// The arguments passed to `wasm-opt`, or `None` if it doesn't run.
+    pub wasm_opt_args: Option<&'a [String]>,
+    /// The binaryen release that `wasm-opt` is pinned to, if any.
+    pub wasm_opt_version: Option<&'a str>,
+    /// The target the bindings are generated for.
+    pub target: Target,
+}
+
+impl Inputs<'_> {
+    /// Hash all of the inputs, including the contents of the `.wasm` file.
+    pub fn fingerprint(&self) -> Result<String> {
+        let wasm = fs::read(self.wasm)
+            .with_context(|| format!("failed to read {}", self.wasm.display()))?;
+
+        let mut hasher = SipHasher13::new();
+        let mut write = |field: &[u8]| {
+            hasher.write_usize(field.len());
+            hasher.write(field);
+        };
+        write(env!("CARGO_PKG_VERSION").as_bytes());
+        write(&wasm);
+        write(self.wasm_bindgen_version.as_bytes());
+        write(self.target.to_string().as_bytes());
+        for flag in self.wasm_bindgen_flags {
+            write(flag.as_bytes());
+        }
+        match self.wasm_opt_args {
+            Some(args) => {
+                write(b"wasm-opt");
+                write(self.wasm_opt_version.unwrap_or_default().as_bytes());
+                for arg in args {
+                    write(arg.as_bytes());
+                }
+            }
+            None => write(b"no wasm-opt"),
+        }
+        Ok(format!("{:032x}", hasher.finish128().as_u128()))
+    }
+}
+
+/// What is recorded about the last time bindings were generated for a target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// The fingerprint of the inputs.
+    pub fingerprint: String,
+    /// The NPM dependencies that `wasm-bindgen` wrote to `package.json`, which
+    /// is replaced by wasm-pack's own `package.json` later on.
+    pub npm_dependencies: Option<HashMap<String, String>>,
+    /// The files and directories that `wasm-bindgen` generated, relative to
+    /// the output directory, which become stale when the bindings are
+    /// generated again.
+    #[serde(default)]
+    pub outputs: Vec<PathBuf>,
+}
+
+/// The fingerprints recorded in an output directory, by target.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Fingerprints {
+    targets: BTreeMap<String, Record>,
+}
+
+impl Fingerprints {
+    /// Read the fingerprints from `out_dir`. Missing or unreadable
+    /// fingerprints are treated as if nothing was recorded.
+    pub fn load(out_dir: &Path) -> Fingerprints {
+        fs::read_to_string(Fingerprints::path(out_dir))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the fingerprints to `out_dir`.
+    pub fn save(&self, out_dir: &Path) -> Result<()> {
+        let path = Fingerprints::path(out_dir);
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// The record for `target`, if there is one.
+    pub fn get(&self, target: Target) -> Option<&Record> {
+        self.targets.get(&target.to_string())
+    }
+
+    /// Replace the record for `target`.
+    pub fn insert(&mut self, target: Target, record: Record) {
+        self.targets.insert(target.to_string(), record);
+    }
+
+    /// Forget the record for `target`.
+    pub fn remove(&mut self, target: Target) -> Option<Record> {
+        self.targets.remove(&target.to_string())
+    }
+
+    /// Forget the records of the targets other than `targets`, returning them.
+    pub fn retain(&mut self, targets: &[Target]) -> Vec<Record> {
+        let names: Vec<String> = targets.iter().map(|target| target.to_string()).collect();
+        let (kept, removed) = std::mem::take(&mut self.targets)
+            .into_iter()
+            .partition(|(name, _)| names.contains(name));
+        self.targets = kept;
+        removed.into_values().collect()
+    }
+
+    /// The outputs recorded for every target, relative to the output
+    /// directory.
+    pub fn outputs(&self) -> impl Iterator<Item = &Path> {
+        self.targets
+            .values()
+            .flat_map(|record| record.outputs.iter().map(|output| output.as_path()))
+    }
+
+    fn path(out_dir: &Path) -> PathBuf {
+        out_dir.join(FINGERPRINT_FILE)
+    }
+}
+
+impl Record {
+    /// Remove the recorded outputs from `out_dir`.
+    pub fn remove_outputs(&self, out_dir: &Path) {
+        for output in &self.outputs {
+            let path = out_dir.join(output);
+            let _ = fs::remove_file(&path).or_else(|_| fs::remove_dir_all(&path));
+        }
+    }
+}
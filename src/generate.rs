@@ -9,8 +9,7 @@ use std::process::Command;
 /// Run `cargo generate` in the current directory to create a new
 /// project from a template
 pub fn generate(template: &str, name: &str, install_status: &install::Status) -> Result<()> {
-    let bin_path = install::get_tool_path(install_status, Tool::CargoGenerate)?
-        .binary(&Tool::CargoGenerate.to_string())?;
+    let bin_path = install::get_tool_path(install_status, Tool::CargoGenerate)?;
     let mut cmd = Command::new(&bin_path);
     cmd.arg("generate");
     cmd.arg("--git").arg(&template);
@@ -0,0 +1,300 @@
+//! Downloading and extracting the archives of prebuilt tools into the cache.
+//!
+//! Entries are laid out the same way as `binary_install::Cache::download` lays
+//! them out, so tools that were cached before are still found, but archives can
+//! also be read from `file://` URLs, so that a local directory can act as a
+//! mirror, and their SHA-256 digest is verified before they are extracted. The
+//! same `.{name}.lock` files are locked while an entry is created.
+
+use crate::config;
+use crate::lock::FileLock;
+use crate::PBAR;
+use anyhow::{anyhow, bail, Context, Result};
+use binary_install::{Cache, Download};
+use flate2::read::GzDecoder;
+use log::{debug, info};
+use ring::digest;
+use siphasher::sip::SipHasher13;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// The suffixes of the files next to an archive that releases publish its
+/// SHA-256 digest in.
+const DIGEST_SUFFIXES: &[&str] = &[".sha256", ".sha256sum"];
+
+/// The tools whose official releases publish no digests, so that none are
+/// looked up unless they are downloaded from a mirror.
+const UNPUBLISHED_DIGESTS: &[&str] = &["cargo-generate", "chromedriver", "geckodriver"];
+
+/// The tools that are downloaded without a warning when their digest is
+/// unknown, since there is nowhere to pin the digest of their latest version.
+const UNVERIFIED_QUIETLY: &[&str] = &["chromedriver", "geckodriver"];
+
+/// Download the archive at `url`, and extract `binaries` from it into a new
+/// entry of the cache called `name`, unless the cache already has an entry for
+/// the URL. Returns `None` if the entry isn't cached and installing isn't
+/// permitted.
+pub fn download(
+    cache: &Cache,
+    install_permitted: bool,
+    name: &str,
+    binaries: &[&str],
+    url: &str,
+) -> Result<Option<Download>> {
+    let dirname = hashed_dirname(url, name);
+    let destination = cache.join(Path::new(&dirname));
+    if destination.exists() {
+        return Ok(Some(Download::at(&destination)));
+    }
+    if !install_permitted {
+        return Ok(None);
+    }
+
+    // Another process may be downloading the same entry, in which case it is
+    // there once the lock is acquired.
+    let _lock = FileLock::exclusive(&cache.join(Path::new(&format!(".{}.lock", dirname))))?;
+    if destination.exists() {
+        return Ok(Some(Download::at(&destination)));
+    }
+
+    let data = fetch(url).with_context(|| format!("failed to download from {}", url))?;
+    verify(
+        name,
+        url,
+        &data,
+        config::get()
+            .mirror(name)
+            .map(|mirror| mirror.source)
+            .as_ref(),
+    )?;
+
+    // Extract everything in a temporary directory in case we're ctrl-c'd.
+    let temp = cache.join(Path::new(&format!(".{}", dirname)));
+    drop(fs::remove_dir_all(&temp));
+    fs::create_dir_all(&temp)?;
+    if url.ends_with(".tar.gz") {
+        extract_tarball(&data, &temp, binaries)
+            .with_context(|| format!("failed to extract tarball from {}", url))?;
+    } else if url.ends_with(".zip") {
+        extract_zip(&data, &temp, binaries)
+            .with_context(|| format!("failed to extract zip from {}", url))?;
+    } else {
+        bail!(
+            "don't know how to extract {}, expected a .tar.gz or .zip file",
+            url
+        );
+    }
+    fs::rename(&temp, &destination)?;
+    Ok(Some(Download::at(&destination)))
+}
+
+/// The path that a `file://` URL points to, if it is one.
+pub fn file_url_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("file://")?;
+    // `file:///C:/tools` is `C:/tools` on Windows.
+    let path = match path.as_bytes() {
+        [b'/', _, b':', ..] if cfg!(windows) => &path[1..],
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Read the contents of `url`, from the network or from a `file://` URL.
+pub fn fetch(url: &str) -> Result<Vec<u8>> {
+    if let Some(path) = file_url_path(url) {
+        return fs::read(&path).with_context(|| anyhow!("failed to read {}", path.display()));
+    }
+
+    let response = ureq::builder()
+        .try_proxy_from_env(true)
+        .build()
+        .get(url)
+        .call()?;
+    let mut data = Vec::new();
+    response.into_reader().read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Check that `data`, the archive of the tool `name` downloaded from `url`, has
+/// the SHA-256 digest that is pinned for it in the config, or else the one
+/// published next to it. Archives without a known digest are used with a
+/// warning, unless they come from the mirror set by `mirror`, which must be
+/// verifiable.
+pub fn verify(name: &str, url: &str, data: &[u8], mirror: Option<&config::Source>) -> Result<()> {
+    let file_name = url.rsplit('/').next().unwrap_or(url);
+    let published = || {
+        if mirror.is_none() && UNPUBLISHED_DIGESTS.contains(&name) {
+            None
+        } else {
+            published_digest(url)
+        }
+    };
+    let (expected, source) = match config::get().checksum(file_name) {
+        Some(setting) => (setting.value, format!("pinned in {}", setting.source)),
+        None => match (published(), mirror) {
+            (Some((digest, digest_url)), _) => (digest, format!("published at {}", digest_url)),
+            (None, Some(mirror)) => bail!(
+                "No SHA-256 digest is published for {}, which is downloaded from the mirror \
+                 set by {}, so it was not extracted. Pin one under [checksums] in \
+                 wasm-pack.toml, or publish it at {}.sha256.",
+                url,
+                mirror,
+                url
+            ),
+            (None, None) if UNVERIFIED_QUIETLY.contains(&name) => {
+                info!("No SHA-256 digest is known for {}", url);
+                return Ok(());
+            }
+            (None, None) => {
+                PBAR.warn(&format!(
+                    "No SHA-256 digest is published for {}, so it can't be verified. \
+                     Pin one under [checksums] in wasm-pack.toml to verify it.",
+                    url
+                ));
+                return Ok(());
+            }
+        },
+    };
+    let actual = sha256(data);
+    if actual != expected {
+        bail!(
+            "The SHA-256 digest of {} is {}, but {} was expected ({}). \
+             The download may be corrupted or tampered with, so it was not extracted.",
+            url,
+            actual,
+            expected,
+            source
+        );
+    }
+    info!("Verified the SHA-256 digest of {}: {}", url, actual);
+    Ok(())
+}
+
+/// The digest published next to the archive at `url`, and where it was found.
+fn published_digest(url: &str) -> Option<(String, String)> {
+    DIGEST_SUFFIXES.iter().find_map(|suffix| {
+        let digest_url = format!("{}{}", url, suffix);
+        let contents = fetch(&digest_url).ok()?;
+        // The file is either the bare digest, or `sha256sum` output with the
+        // file name after the digest.
+        let digest = String::from_utf8_lossy(&contents)
+            .split_whitespace()
+            .next()?
+            .to_ascii_lowercase();
+        if digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            Some((digest, digest_url))
+        } else {
+            debug!("{} is not a SHA-256 digest", digest_url);
+            None
+        }
+    })
+}
+
+/// The SHA-256 digest of `data`, in lowercase hexadecimal.
+pub fn sha256(data: &[u8]) -> String {
+    digest::digest(&digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The name of the cache entry for `url`, which is the same one that
+/// `binary_install` uses.
+fn hashed_dirname(url: &str, name: &str) -> String {
+    let mut hasher = SipHasher13::new();
+    url.hash(&mut hasher);
+    let hex: String = hasher
+        .finish()
+        .to_le_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("{}-{}", name, hex)
+}
+
+fn extract_tarball(tarball: &[u8], dst: &Path, binaries: &[&str]) -> Result<()> {
+    let mut binaries: HashSet<&str> = binaries.iter().copied().collect();
+    let mut archive = tar::Archive::new(GzDecoder::new(tarball));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let dest = match extract_binary(&entry.path()?, dst, &mut binaries) {
+            Some(dest) => dest,
+            None => continue,
+        };
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(dest)?;
+    }
+    check_all_extracted(&binaries, "tarball")
+}
+
+fn extract_zip(zip: &[u8], dst: &Path, binaries: &[&str]) -> Result<()> {
+    let mut binaries: HashSet<&str> = binaries.iter().copied().collect();
+    let mut archive = zip::ZipArchive::new(io::Cursor::new(zip))?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let entry_path = match entry.enclosed_name() {
+            Some(path) => path,
+            None => continue,
+        };
+        let dest = match extract_binary(&entry_path, dst, &mut binaries) {
+            Some(dest) => dest,
+            None => continue,
+        };
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = executable_options().open(dest)?;
+        io::copy(&mut entry, &mut file)?;
+    }
+    check_all_extracted(&binaries, "zip")
+}
+
+#[cfg(unix)]
+fn executable_options() -> fs::OpenOptions {
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true).mode(0o755);
+    options
+}
+
+#[cfg(not(unix))]
+fn executable_options() -> fs::OpenOptions {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    options
+}
+
+/// Where to extract the file at `entry_path` in an archive to, if it is one of
+/// the `binaries`, which are either file names without an extension or paths
+/// within the archive.
+fn extract_binary(entry_path: &Path, dst: &Path, binaries: &mut HashSet<&str>) -> Option<PathBuf> {
+    let file_stem = entry_path.file_stem()?;
+    let binary = binaries.iter().copied().find(|binary| {
+        *binary == file_stem || (binary.contains('/') && entry_path.ends_with(binary))
+    })?;
+    binaries.remove(binary);
+    if binary.contains('/') {
+        Some(dst.join(binary))
+    } else {
+        Some(dst.join(entry_path.file_name()?))
+    }
+}
+
+fn check_all_extracted(binaries: &HashSet<&str>, kind: &str) -> Result<()> {
+    if binaries.is_empty() {
+        return Ok(());
+    }
+    let mut missing: Vec<&str> = binaries.iter().copied().collect();
+    missing.sort();
+    bail!(
+        "the {} was missing expected executables: {}",
+        kind,
+        missing.join(", ")
+    )
+}
@@ -1,9 +1,13 @@
 //! Functionality related to installing prebuilt binaries and/or running cargo install.
 
 use self::krate::Krate;
+use crate::cache::{self, EntrySource};
 use crate::child;
+use crate::config;
 use crate::emoji;
 use crate::install;
+use crate::lock::FileLock;
+use crate::progressbar::Event;
 use crate::PBAR;
 use anyhow::{anyhow, bail, Context, Result};
 use binary_install::{Cache, Download};
@@ -11,11 +15,12 @@ use log::debug;
 use log::{info, warn};
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use which::which;
 
 mod arch;
+pub mod download;
 mod krate;
 mod mode;
 mod os;
@@ -25,6 +30,11 @@ pub use self::mode::InstallMode;
 pub use self::os::Os;
 pub use self::tool::Tool;
 
+/// The binaryen release that `wasm-opt` is downloaded from, unless the crate
+/// sets `wasm-opt-version`.
+// Make sure to update the version in docs/src/cargo-toml-configuration.md as well
+pub const DEFAULT_WASM_OPT_VERSION: &str = "version_117";
+
 /// Possible outcomes of attempting to find/install a tool
 pub enum Status {
     /// Couldn't install tool because downloads are forbidden by user
@@ -33,12 +43,17 @@ pub enum Status {
     PlatformNotSupported,
     /// We found the tool at the specified path
     Found(Download),
+    /// The binary of the tool is set with `WASM_PACK_<TOOL>` or in the config,
+    /// and may not have the tool's usual file name
+    Configured(PathBuf),
 }
 
-/// Handles possible installs status and returns the download or a error message
-pub fn get_tool_path(status: &Status, tool: Tool) -> Result<&Download> {
+/// Handles possible installs status and returns the path of the tool's binary
+/// or a error message
+pub fn get_tool_path(status: &Status, tool: Tool) -> Result<PathBuf> {
     match status {
-        Status::Found(download) => Ok(download),
+        Status::Found(download) => download.binary(&tool.to_string()),
+        Status::Configured(path) => Ok(path.clone()),
         Status::CannotInstall => bail!("Not able to find or install a local {}.", tool),
         install::Status::PlatformNotSupported => {
             bail!("{} does not currently support your platform.", tool)
@@ -58,6 +73,81 @@ pub fn download_prebuilt_or_cargo_install(
     version: &str,
     install_permitted: bool,
 ) -> Result<Status> {
+    let name = tool.to_string();
+    let status = find_or_install(tool, cache, version, install_permitted)?;
+    let path = match &status {
+        Status::Found(download) => download.binary(&name).ok(),
+        Status::Configured(path) => Some(path.clone()),
+        _ => None,
+    };
+    if let Some(path) = path {
+        let version = Some(version).filter(|version| *version != "latest");
+        report_tool(&name, &path, version);
+    }
+    Ok(status)
+}
+
+/// Report the binary that was found for a tool as a JSON event.
+pub fn report_tool(tool: &str, path: &Path, version: Option<&str>) {
+    PBAR.event(&Event::ToolResolved {
+        tool,
+        path,
+        version,
+    });
+}
+
+/// The binary configured for `tool` with `WASM_PACK_<TOOL>` or in the config,
+/// where `key` is one of [`config::TOOLS`], if there is one.
+///
+/// The binary must be `version`. Any version will do for `latest`, which
+/// isn't looked up, so that a configured binary never needs the network.
+pub fn configured_tool(tool: &Tool, key: &str, version: &str) -> Result<Option<PathBuf>> {
+    let setting = match config::get().tool(key) {
+        Some(setting) => setting,
+        None => return Ok(None),
+    };
+    let path = setting.value;
+    if !path.is_file() {
+        bail!(
+            "The {} set by {} doesn't exist: {}",
+            key,
+            setting.source,
+            path.display()
+        );
+    }
+    if version != "latest" {
+        let actual = get_cli_version(tool, &path)?;
+        if actual != version {
+            bail!(
+                "The {} set by {} is {}, but {} is required: {}",
+                key,
+                setting.source,
+                actual,
+                version,
+                path.display()
+            );
+        }
+    }
+    info!(
+        "Using the {} set by {}: {}",
+        key,
+        setting.source,
+        path.display()
+    );
+    Ok(Some(path))
+}
+
+fn find_or_install(
+    tool: Tool,
+    cache: &Cache,
+    version: &str,
+    install_permitted: bool,
+) -> Result<Status> {
+    // A binary set in the config or environment takes precedence.
+    if let Some(path) = configured_tool(&tool, &tool.to_string(), version)? {
+        return Ok(Status::Configured(path));
+    }
+
     // If the tool is installed globally and it has the right version, use
     // that. Assume that other tools are installed next to it.
     //
@@ -71,6 +161,17 @@ pub fn download_prebuilt_or_cargo_install(
         }
     }
 
+    install_to_cache(tool, cache, version, install_permitted)
+}
+
+/// Install a tool into the cache, even if it is installed globally: download a
+/// prebuilt binary if there is one, and fall back to `cargo install`.
+pub fn install_to_cache(
+    tool: Tool,
+    cache: &Cache,
+    version: &str,
+    install_permitted: bool,
+) -> Result<Status> {
     let msg = format!("{}Installing {}...", emoji::DOWN_ARROW, tool);
     PBAR.info(&msg);
 
@@ -110,6 +211,12 @@ pub fn get_cli_version(tool: &Tool, path: &Path) -> Result<String> {
     let mut cmd = Command::new(path);
     cmd.arg("--version");
     let stdout = child::run_capture_stdout(cmd, tool)?;
+    if let Tool::WasmOpt = tool {
+        // `wasm-opt version 117 (version_117)`, named like binaryen's releases.
+        if let Some(number) = stdout.split_whitespace().nth(2) {
+            return Ok(format!("version_{}", number));
+        }
+    }
     let version = stdout.trim().split_whitespace().nth(1);
     match version {
         Some(v) => Ok(v.to_string()),
@@ -132,18 +239,18 @@ pub fn download_prebuilt(
             e,
         ),
     };
-    match tool {
+    let status = match tool {
         Tool::WasmBindgen => {
             let binaries = &["wasm-bindgen", "wasm-bindgen-test-runner"];
-            match cache.download(install_permitted, "wasm-bindgen", binaries, &url)? {
-                Some(download) => Ok(Status::Found(download)),
+            match download::download(cache, install_permitted, "wasm-bindgen", binaries, &url)? {
+                Some(download) => Status::Found(download),
                 None => bail!("wasm-bindgen v{} is not installed!", version),
             }
         }
         Tool::CargoGenerate => {
             let binaries = &["cargo-generate"];
-            match cache.download(install_permitted, "cargo-generate", binaries, &url)? {
-                Some(download) => Ok(Status::Found(download)),
+            match download::download(cache, install_permitted, "cargo-generate", binaries, &url)? {
+                Some(download) => Status::Found(download),
                 None => bail!("cargo-generate v{} is not installed!", version),
             }
         }
@@ -153,26 +260,64 @@ pub fn download_prebuilt(
                 Os::Linux => &["bin/wasm-opt"],
                 Os::Windows => &["bin/wasm-opt.exe"],
             };
-            match cache.download(install_permitted, "wasm-opt", binaries, &url)? {
-                Some(download) => Ok(Status::Found(download)),
+            match download::download(cache, install_permitted, "wasm-opt", binaries, &url)? {
+                Some(download) => Status::Found(download),
                 // TODO(ag_dubs): why is this different? i forget...
-                None => Ok(Status::CannotInstall),
+                None => Status::CannotInstall,
             }
         }
+    };
+    if let Status::Found(download) = &status {
+        cache::record_use(
+            &download.path(),
+            &tool.to_string(),
+            Some(prebuilt_version(tool, version)),
+            EntrySource::Download,
+        );
     }
+    Ok(status)
 }
 
 /// Returns the URL of a precompiled version of wasm-bindgen, if we have one
 /// available for our host platform.
+///
+/// A mirror configured for the tool takes the place of its official releases.
 fn prebuilt_url(tool: &Tool, version: &str) -> Result<String> {
     let os = Os::get()?;
     let arch = Arch::get()?;
-    prebuilt_url_for(tool, version, &arch, &os)
+    let mirror_url = config::get().mirror_url(
+        &tool.to_string(),
+        prebuilt_version(tool, version),
+        prebuilt_target(tool, &arch, &os)?,
+    );
+    match mirror_url {
+        Some(url) => Ok(url),
+        None => prebuilt_url_for(tool, version, &arch, &os),
+    }
 }
 
-/// Get the download URL for some tool at some version, architecture and operating system
-pub fn prebuilt_url_for(tool: &Tool, version: &str, arch: &Arch, os: &Os) -> Result<String> {
-    let target = match (os, arch, tool) {
+/// The version of `tool` that is downloaded when `version` is asked for.
+fn prebuilt_version<'a>(tool: &Tool, version: &'a str) -> &'a str {
+    match tool {
+        Tool::WasmBindgen => version,
+        Tool::CargoGenerate => "0.18.2",
+        Tool::WasmOpt if version == "latest" => DEFAULT_WASM_OPT_VERSION,
+        Tool::WasmOpt => version,
+    }
+}
+
+/// Whether `tool` has prebuilt binaries for this platform.
+pub fn has_prebuilt(tool: &Tool) -> bool {
+    match (Os::get(), Arch::get()) {
+        (Ok(os), Ok(arch)) => prebuilt_target(tool, &arch, &os).is_ok(),
+        _ => false,
+    }
+}
+
+/// The name that the releases of `tool` use for the given architecture and
+/// operating system.
+fn prebuilt_target(tool: &Tool, arch: &Arch, os: &Os) -> Result<&'static str> {
+    Ok(match (os, arch, tool) {
         (Os::Linux, Arch::AArch64, Tool::WasmOpt) => "aarch64-linux",
         (Os::Linux, Arch::AArch64, _) => "aarch64-unknown-linux-gnu",
         (Os::Linux, Arch::X86_64, Tool::WasmOpt) => "x86_64-linux",
@@ -184,7 +329,13 @@ pub fn prebuilt_url_for(tool: &Tool, version: &str, arch: &Arch, os: &Os) -> Res
         (Os::Windows, Arch::X86_64, Tool::WasmOpt) => "x86_64-windows",
         (Os::Windows, Arch::X86_64, _) => "x86_64-pc-windows-msvc",
         _ => bail!("Unrecognized target!"),
-    };
+    })
+}
+
+/// Get the download URL for some tool at some version, architecture and operating system
+pub fn prebuilt_url_for(tool: &Tool, version: &str, arch: &Arch, os: &Os) -> Result<String> {
+    let target = prebuilt_target(tool, arch, os)?;
+    let version = prebuilt_version(tool, version);
     match tool {
         Tool::WasmBindgen => {
             Ok(format!(
@@ -196,20 +347,66 @@ pub fn prebuilt_url_for(tool: &Tool, version: &str, arch: &Arch, os: &Os) -> Res
         Tool::CargoGenerate => {
             Ok(format!(
                 "https://github.com/cargo-generate/cargo-generate/releases/download/v{0}/cargo-generate-v{0}-{1}.tar.gz",
-                "0.18.2",
+                version,
                 target
             ))
         },
         Tool::WasmOpt => {
             Ok(format!(
         "https://github.com/WebAssembly/binaryen/releases/download/{vers}/binaryen-{vers}-{target}.tar.gz",
-        vers = "version_117", // Make sure to update the version in docs/src/cargo-toml-configuration.md as well
+        vers = version,
         target = target,
             ))
         }
     }
 }
 
+/// The binaryen releases that the `wasm-opt` crate is published for. It is
+/// versioned after them, so `version_116` is published as 0.116.x.
+const WASM_OPT_CRATE_RELEASES: &[u32] = &[110, 111, 112, 113, 114, 116];
+
+/// The version requirement to pass to `cargo install` for `version` of `tool`,
+/// if there is one.
+///
+/// Binaryen releases newer than the last one the `wasm-opt` crate is published
+/// for are installed as that last release, while older ones without a release
+/// of the crate can't be installed with cargo.
+pub fn cargo_install_version(tool: &Tool, version: &str) -> Result<Option<String>> {
+    match tool {
+        Tool::WasmOpt => {
+            let release: u32 = match version.strip_prefix("version_") {
+                Some(release) => release
+                    .parse()
+                    .with_context(|| anyhow!("invalid binaryen version: {}", version))?,
+                None => return Ok(None),
+            };
+            let latest = WASM_OPT_CRATE_RELEASES[WASM_OPT_CRATE_RELEASES.len() - 1];
+            if release > latest {
+                PBAR.warn(&format!(
+                    "The wasm-opt crate isn't published for binaryen {}, so the one for \
+                     binaryen version_{} is installed instead.",
+                    version, latest
+                ));
+                return Ok(Some(format!("~0.{}", latest)));
+            }
+            if !WASM_OPT_CRATE_RELEASES.contains(&release) {
+                bail!(
+                    "The wasm-opt crate isn't published for binaryen {}, so it can't be \
+                     installed with cargo. Use one of binaryen version_{} to version_{}, \
+                     or point the `wasm-opt` setting of wasm-pack.toml at a wasm-opt \
+                     binary.",
+                    version,
+                    WASM_OPT_CRATE_RELEASES[0],
+                    latest
+                );
+            }
+            Ok(Some(format!("~0.{}", release)))
+        }
+        _ if version == "latest" => Ok(None),
+        _ => Ok(Some(version.to_string())),
+    }
+}
+
 /// Use `cargo install` to install the tool locally into the given
 /// crate.
 pub fn cargo_install(
@@ -232,6 +429,12 @@ pub fn cargo_install(
             version,
             destination.display()
         );
+        cache::record_use(
+            &destination,
+            &tool.to_string(),
+            Some(version),
+            EntrySource::CargoInstall,
+        );
         let download = Download::at(&destination);
         return Ok(Status::Found(download));
     }
@@ -240,6 +443,17 @@ pub fn cargo_install(
         return Ok(Status::CannotInstall);
     }
 
+    let _lock = FileLock::exclusive(&cache.join(format!(".{}.lock", dirname).as_ref()))?;
+    if destination.exists() {
+        debug!(
+            "another process installed {} at {}",
+            tool,
+            destination.display()
+        );
+        let download = Download::at(&destination);
+        return Ok(Status::Found(download));
+    }
+
     // Run `cargo install` to a temporary location to handle ctrl-c gracefully
     // and ensure we don't accidentally use stale files in the future
     let tmp = cache.join(format!(".{}", dirname).as_ref());
@@ -261,7 +475,7 @@ pub fn cargo_install(
         .arg("--root")
         .arg(&tmp);
 
-    if version != "latest" {
+    if let Some(version) = cargo_install_version(&tool, version)? {
         cmd.arg("--version").arg(version);
     }
 
@@ -271,14 +485,15 @@ pub fn cargo_install(
     // `cargo install` will put the installed binaries in `$root/bin/*`, but we
     // just want them in `$root/*` directly (which matches how the tarballs are
     // laid out, and where the rest of our code expects them to be). So we do a
-    // little renaming here.
-    let binaries: Result<Vec<&str>> = match tool {
-        Tool::WasmBindgen => Ok(vec!["wasm-bindgen", "wasm-bindgen-test-runner"]),
-        Tool::CargoGenerate => Ok(vec!["cargo-generate"]),
-        Tool::WasmOpt => bail!("Cannot install wasm-opt with cargo."),
+    // little renaming here. wasm-opt is the exception, because binaryen's
+    // tarballs have it in `bin/` too.
+    let binaries: &[&str] = match tool {
+        Tool::WasmBindgen => &["wasm-bindgen", "wasm-bindgen-test-runner"],
+        Tool::CargoGenerate => &["cargo-generate"],
+        Tool::WasmOpt => &[],
     };
 
-    for b in binaries?.iter().cloned() {
+    for b in binaries.iter().cloned() {
         let from = tmp
             .join("bin")
             .join(b)
@@ -296,6 +511,12 @@ pub fn cargo_install(
 
     // Finally, move the `tmp` directory into our binary cache.
     fs::rename(&tmp, &destination)?;
+    cache::record_use(
+        &destination,
+        &tool.to_string(),
+        Some(version),
+        EntrySource::CargoInstall,
+    );
 
     let download = Download::at(&destination);
     Ok(Status::Found(download))
@@ -14,8 +14,10 @@ extern crate which;
 #[macro_use]
 extern crate serde_derive;
 extern crate binary_install;
+extern crate brotli;
 extern crate chrono;
 extern crate dialoguer;
+extern crate flate2;
 extern crate log;
 extern crate serde_ignored;
 extern crate serde_json;
@@ -27,21 +29,27 @@ pub mod build;
 pub mod cache;
 pub mod child;
 pub mod command;
+pub mod config;
+pub mod doctor;
 pub mod emoji;
+pub mod fingerprint;
 pub mod generate;
 pub mod install;
 pub mod license;
+pub mod lock;
 pub mod lockfile;
 pub mod manifest;
 pub mod npm;
 pub mod progressbar;
 pub mod readme;
+pub mod size;
 pub mod stamps;
 pub mod target;
 pub mod test;
 pub mod wasm_opt;
+pub mod watch;
 
-use crate::progressbar::{LogLevel, ProgressOutput};
+use crate::progressbar::{LogLevel, MessageFormat, ProgressOutput};
 use clap::builder::ArgAction;
 use clap::Parser;
 
@@ -64,7 +72,11 @@ pub struct Cli {
     /// No output printed to stdout
     pub quiet: bool,
 
-    #[clap(long = "log-level", default_value = "info")]
-    /// The maximum level of messages that should be logged by wasm-pack. [possible values: info, warn, error]
-    pub log_level: LogLevel,
+    #[clap(long = "log-level")]
+    /// The maximum level of messages that should be logged by wasm-pack. Defaults to `info`, or the `log-level` in wasm-pack's config. [possible values: info, warn, error]
+    pub log_level: Option<LogLevel>,
+
+    #[clap(long = "message-format", default_value = "human")]
+    /// The format of wasm-pack's messages: styled text, or one JSON event per line on stdout. [possible values: human, json]
+    pub message_format: MessageFormat,
 }
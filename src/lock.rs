@@ -0,0 +1,92 @@
+//! Advisory file locks and atomic writes, so that several wasm-pack processes
+//! can share a cache and the stamps file without seeing each other's work
+//! half done.
+
+use crate::PBAR;
+use anyhow::{anyhow, Context, Result};
+use fs4::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Tells apart the temporary files of threads in the same process.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// An exclusive lock on a file, which is released when it is dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Lock the file at `path`, creating it if it doesn't exist, and wait for
+    /// other processes that hold the lock to release it.
+    pub fn exclusive(path: &Path) -> Result<FileLock> {
+        let file = open(path)?;
+        match file.try_lock_exclusive() {
+            Ok(()) => return Ok(FileLock { file }),
+            Err(e) if is_contended(&e) => {}
+            Err(e) => return Err(e).with_context(|| anyhow!("failed to lock {}", path.display())),
+        }
+        PBAR.info(&format!(
+            "Waiting for another wasm-pack to release {}...",
+            path.display()
+        ));
+        file.lock_exclusive()
+            .with_context(|| anyhow!("failed to lock {}", path.display()))?;
+        Ok(FileLock { file })
+    }
+
+    /// Lock the file at `path`, creating it if it doesn't exist, unless
+    /// another process holds the lock, in which case `None` is returned.
+    pub fn try_exclusive(path: &Path) -> Result<Option<FileLock>> {
+        let file = open(path)?;
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Some(FileLock { file })),
+            Err(e) if is_contended(&e) => Ok(None),
+            Err(e) => Err(e).with_context(|| anyhow!("failed to lock {}", path.display())),
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        drop(self.file.unlock());
+    }
+}
+
+fn open(path: &Path) -> Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| anyhow!("failed to open the lock file {}", path.display()))
+}
+
+fn is_contended(e: &io::Error) -> bool {
+    e.raw_os_error() == fs4::lock_contended_error().raw_os_error()
+}
+
+/// Write `contents` to `path` through a temporary file next to it, so that
+/// readers see either the old or the new contents, never a mix of both.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", path.display()))?;
+    let temp = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&temp, contents).with_context(|| anyhow!("failed to write {}", temp.display()))?;
+    fs::rename(&temp, path).with_context(|| {
+        drop(fs::remove_file(&temp));
+        anyhow!("failed to replace {}", path.display())
+    })
+}
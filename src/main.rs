@@ -14,9 +14,12 @@ use std::env;
 use std::panic;
 use std::sync::mpsc;
 use std::thread;
+use std::time::Instant;
 use wasm_pack::{
     build::{self, WasmPackVersion},
-    command::run_wasm_pack,
+    command::{run_wasm_pack, Command},
+    config,
+    progressbar::{self, ErrorMessage, Event},
     Cli, PBAR,
 };
 
@@ -29,10 +32,7 @@ fn background_check_for_updates() -> mpsc::Receiver<Result<WasmPackVersion>> {
         let wasm_pack_version = build::check_wasm_pack_versions();
 
         if let Ok(wasm_pack_version) = wasm_pack_version {
-            if !wasm_pack_version.local.is_empty()
-                && !wasm_pack_version.latest.is_empty()
-                && wasm_pack_version.local != wasm_pack_version.latest
-            {
+            if wasm_pack_version.is_outdated() {
                 let _ = sender.send(Ok(wasm_pack_version));
             }
         } else {
@@ -48,18 +48,35 @@ fn main() {
 
     setup_panic_hooks();
 
-    if let Err(e) = run() {
-        eprintln!("Error: {}", e);
-        for cause in e.chain() {
-            eprintln!("Caused by: {}", cause);
+    let started = Instant::now();
+    let result = run();
+
+    if PBAR.is_json() {
+        PBAR.event(&Event::Summary {
+            success: result.is_ok(),
+            duration_ms: progressbar::duration_ms(started.elapsed()),
+            errors: result
+                .as_ref()
+                .err()
+                .map(ErrorMessage::from)
+                .into_iter()
+                .collect(),
+        });
+    }
+
+    if let Err(e) = result {
+        // In JSON mode the error was already reported in the summary.
+        if !PBAR.is_json() {
+            eprintln!("Error: {}", e);
+            for cause in e.chain() {
+                eprintln!("Caused by: {}", cause);
+            }
         }
         ::std::process::exit(1);
     }
 }
 
 fn run() -> Result<()> {
-    let wasm_pack_version = background_check_for_updates();
-
     // Deprecate `init`
     if let Some("init") = env::args().nth(1).as_ref().map(|arg| arg.as_str()) {
         println!("wasm-pack init is deprecated, consider using wasm-pack build");
@@ -80,7 +97,19 @@ fn run() -> Result<()> {
 
     let args = Cli::parse();
 
-    PBAR.set_log_level(args.log_level);
+    PBAR.set_message_format(args.message_format);
+    let config_dir = args.cmd.config_dir()?;
+    let config = if args.cmd.diagnoses_config() {
+        config::init_or_default(&config_dir)
+    } else {
+        config::init(&config_dir)?
+    };
+    PBAR.set_log_level(args.log_level.unwrap_or(config.log_level().value));
+
+    // `self check-update` checks for updates itself.
+    let wasm_pack_version = (config.update_check().value
+        && !matches!(args.cmd, Command::SelfCommand(_)))
+    .then(background_check_for_updates);
 
     if args.quiet {
         PBAR.set_quiet(true);
@@ -88,7 +117,7 @@ fn run() -> Result<()> {
 
     run_wasm_pack(args.cmd)?;
 
-    if let Ok(wasm_pack_version) = wasm_pack_version.try_recv() {
+    if let Some(Ok(wasm_pack_version)) = wasm_pack_version.map(|receiver| receiver.try_recv()) {
         match wasm_pack_version {
             Ok(wasm_pack_version) =>
                 PBAR.warn(&format!("There's a newer version of wasm-pack available, the new version is: {}, you are using: {}. \
@@ -9,26 +9,32 @@
 use anyhow::{anyhow, bail, Context, Result};
 mod npm;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{collections::HashMap, fs};
 
 use self::npm::{
-    repository::Repository, CommonJSPackage, ESModulesPackage, NoModulesPackage, NpmPackage,
+    repository::Repository, CommonJSPackage, ESModulesPackage, ExportConditions, Exports,
+    MultiTargetPackage, NoModulesPackage, NpmPackage,
 };
+use crate::build::wasm_target::is_custom_target;
 use crate::command::build::{BuildProfile, Target};
+use crate::config;
+use crate::install::download;
+use crate::size::SizeBudget;
+use crate::stamps;
 use crate::PBAR;
 use cargo_metadata::Metadata;
 use chrono::offset;
 use chrono::DateTime;
+use path_clean::PathClean;
 use serde::{self, Deserialize};
 use serde_json;
-use std::collections::BTreeSet;
-use std::env;
-use std::io::Write;
+use std::collections::{BTreeMap, BTreeSet};
 use strsim::levenshtein;
 use toml;
 
 const WASM_PACK_METADATA_KEY: &str = "package.metadata.wasm-pack";
+const WORKSPACE_WASM_PACK_METADATA_KEY: &str = "workspace.metadata.wasm-pack";
 const WASM_PACK_VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 const WASM_PACK_REPO_URL: &str = "https://github.com/rustwasm/wasm-pack";
 
@@ -36,8 +42,11 @@ const WASM_PACK_REPO_URL: &str = "https://github.com/rustwasm/wasm-pack";
 pub struct CrateData {
     data: Metadata,
     current_idx: usize,
-    manifest: CargoManifest,
     out_name: Option<String>,
+    profiles: HashMap<String, CargoWasmPackProfile>,
+    /// The names of the profiles with a table in the wasm-pack metadata.
+    profile_tables: BTreeSet<String>,
+    build_defaults: CargoWasmPack,
 }
 
 #[doc(hidden)]
@@ -60,61 +69,136 @@ struct CargoMetadata {
     wasm_pack: CargoWasmPack,
 }
 
-#[derive(Default, Deserialize)]
-struct CargoWasmPack {
+/// The `[package.metadata.wasm-pack]` table, or `[workspace.metadata.wasm-pack]`
+/// for the settings the package doesn't have.
+///
+/// Apart from the profiles, these are the project's defaults for the options
+/// of `wasm-pack build`, which the options given on the command line override.
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CargoWasmPack {
+    /// The profile tables as written in `Cargo.toml`, keyed by profile name,
+    /// without any defaults filled in.
     #[serde(default)]
-    profile: CargoWasmPackProfiles,
+    profile: BTreeMap<String, CargoWasmPackProfile>,
+    /// `target`: one target, a comma-separated list or an array of targets.
+    #[serde(default, deserialize_with = "deserialize_targets")]
+    pub target: Option<Vec<Target>>,
+    /// `scope`: the npm scope.
+    pub scope: Option<String>,
+    /// `out-dir`: the output directory.
+    pub out_dir: Option<String>,
+    /// `out-name`: the output file names.
+    pub out_name: Option<String>,
+    /// `no-typescript`: don't generate a `.d.ts` file.
+    pub no_typescript: Option<bool>,
+    /// `weak-refs`: enable the JS weak references proposal.
+    pub weak_refs: Option<bool>,
+    /// `reference-types`: enable WebAssembly reference types.
+    pub reference_types: Option<bool>,
+    /// `no-pack`: don't generate a `package.json`.
+    pub no_pack: Option<bool>,
+    /// `no-opt`: don't run `wasm-opt`.
+    pub no_opt: Option<bool>,
+    /// `rust-target`: the target triple or custom target JSON to compile for.
+    pub rust_target: Option<String>,
+    /// `cargo-args`: extra options passed to `cargo build`, before the ones
+    /// given on the command line.
+    pub cargo_args: Option<Vec<String>>,
+    /// `wasm-opt-version`: the binaryen release to run `wasm-opt` from, such
+    /// as `version_119`.
+    pub wasm_opt_version: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct CargoWasmPackProfiles {
-    #[serde(
-        default = "CargoWasmPackProfile::default_dev",
-        deserialize_with = "CargoWasmPackProfile::deserialize_dev"
-    )]
-    dev: CargoWasmPackProfile,
-
-    #[serde(
-        default = "CargoWasmPackProfile::default_release",
-        deserialize_with = "CargoWasmPackProfile::deserialize_release"
-    )]
-    release: CargoWasmPackProfile,
-
-    #[serde(
-        default = "CargoWasmPackProfile::default_profiling",
-        deserialize_with = "CargoWasmPackProfile::deserialize_profiling"
-    )]
-    profiling: CargoWasmPackProfile,
-
-    #[serde(
-        default = "CargoWasmPackProfile::default_custom",
-        deserialize_with = "CargoWasmPackProfile::deserialize_custom"
-    )]
-    custom: CargoWasmPackProfile,
-}
+impl CargoWasmPack {
+    /// Make a relative custom target JSON file in `rust-target` relative to
+    /// `dir`, the directory of the `Cargo.toml` that sets it.
+    fn resolve_paths(&mut self, dir: &Path) {
+        if let Some(rust_target) = &self.rust_target {
+            if is_custom_target(rust_target) && Path::new(rust_target).is_relative() {
+                let path = dir.join(rust_target).clean();
+                self.rust_target = Some(path.to_string_lossy().into_owned());
+            }
+        }
+    }
 
-impl Default for CargoWasmPackProfiles {
-    fn default() -> CargoWasmPackProfiles {
-        CargoWasmPackProfiles {
-            dev: CargoWasmPackProfile::default_dev(),
-            release: CargoWasmPackProfile::default_release(),
-            profiling: CargoWasmPackProfile::default_profiling(),
-            custom: CargoWasmPackProfile::default_custom(),
+    /// Fill in the settings missing from `self` with those of `fallback`.
+    fn or(self, fallback: CargoWasmPack) -> CargoWasmPack {
+        let mut profile = fallback.profile;
+        profile.extend(self.profile);
+        CargoWasmPack {
+            profile,
+            target: self.target.or(fallback.target),
+            scope: self.scope.or(fallback.scope),
+            out_dir: self.out_dir.or(fallback.out_dir),
+            out_name: self.out_name.or(fallback.out_name),
+            no_typescript: self.no_typescript.or(fallback.no_typescript),
+            weak_refs: self.weak_refs.or(fallback.weak_refs),
+            reference_types: self.reference_types.or(fallback.reference_types),
+            no_pack: self.no_pack.or(fallback.no_pack),
+            no_opt: self.no_opt.or(fallback.no_opt),
+            rust_target: self.rust_target.or(fallback.rust_target),
+            cargo_args: self.cargo_args.or(fallback.cargo_args),
+            wasm_opt_version: self.wasm_opt_version.or(fallback.wasm_opt_version),
         }
     }
 }
 
+fn deserialize_targets<'de, D>(deserializer: D) -> Result<Option<Vec<Target>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Targets {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let names = match Targets::deserialize(deserializer)? {
+        Targets::One(names) => names.split(',').map(str::to_string).collect(),
+        Targets::Many(names) => names,
+    };
+    names
+        .iter()
+        .map(|name| name.trim().parse().map_err(serde::de::Error::custom))
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// The `[profile.*]` tables of the workspace root's `Cargo.toml`, which is
+/// where cargo reads custom profiles from.
+#[derive(Default, Deserialize)]
+struct CargoProfiles {
+    #[serde(default)]
+    profile: BTreeMap<String, CargoProfile>,
+}
+
+#[derive(Deserialize)]
+struct CargoProfile {
+    inherits: Option<String>,
+}
+
+/// The name of the profile table from before tables could be named after any
+/// profile. It is only used by `--profile custom`, and is accepted without a
+/// `[profile.custom]` table in cargo.
+const LEGACY_CUSTOM_PROFILE: &str = "custom";
+
 /// This is where configuration goes for wasm-bindgen, wasm-opt, wasm-snip, or
 /// anything else that wasm-pack runs.
-#[derive(Default, Deserialize)]
+#[derive(Clone, Default, Deserialize)]
 pub struct CargoWasmPackProfile {
     #[serde(default, rename = "wasm-bindgen")]
     wasm_bindgen: CargoWasmPackProfileWasmBindgen,
     #[serde(default, rename = "wasm-opt")]
     wasm_opt: Option<CargoWasmPackProfileWasmOpt>,
+    #[serde(default, rename = "size-budget")]
+    size_budget: Option<SizeBudget>,
+    #[serde(default)]
+    threads: Option<bool>,
 }
 
-#[derive(Default, Deserialize)]
+#[derive(Clone, Default, Deserialize)]
 struct CargoWasmPackProfileWasmBindgen {
     #[serde(default, rename = "debug-js-glue")]
     debug_js_glue: Option<bool>,
@@ -145,77 +229,71 @@ struct CrateInformation {
 }
 
 impl Crate {
-    /// Returns latest wasm-pack version
+    /// Returns latest wasm-pack version, as of the last lookup if it was made
+    /// within the configured `update-check-interval`.
     pub fn return_wasm_pack_latest_version() -> Result<Option<String>> {
         let current_time = chrono::offset::Local::now();
-        let old_metadata_file = Self::return_wasm_pack_file();
-
-        match old_metadata_file {
-            Some(ref file_contents) => {
-                let last_updated = Self::return_stamp_file_value(&file_contents, "created")
-                    .and_then(|t| DateTime::parse_from_str(t.as_str(), "%+").ok());
-
-                last_updated
-                    .map(|last_updated| {
-                        if current_time.signed_duration_since(last_updated).num_hours() > 24 {
-                            Self::return_api_call_result(current_time).map(Some)
-                        } else {
-                            Ok(Self::return_stamp_file_value(&file_contents, "version"))
-                        }
-                    })
-                    .unwrap_or_else(|| Ok(None))
+        let stamps = stamps::read_stamps_file_to_json().ok();
+        let stamp = |key| {
+            stamps
+                .as_ref()
+                .and_then(|json| stamps::get_stamp_value(key, json).ok())
+        };
+
+        let interval = config::get().update_check_interval().value;
+        let last_checked = stamp(stamps::WASM_PACK_LAST_CHECKED_STAMP)
+            .and_then(|t| DateTime::parse_from_str(t.as_str(), "%+").ok());
+        match last_checked {
+            // A check in the future means the clock was changed, so it is
+            // treated as a recent one.
+            Some(last_checked)
+                if current_time
+                    .signed_duration_since(last_checked)
+                    .to_std()
+                    .map_or(true, |elapsed| elapsed < interval) =>
+            {
+                Ok(stamp(stamps::WASM_PACK_LATEST_VERSION_STAMP))
             }
-            None => Self::return_api_call_result(current_time).map(Some),
+            _ => Self::return_api_call_result(current_time).map(Some),
         }
     }
 
+    /// Look up the latest wasm-pack version right away, however recently it
+    /// was last looked up, and save it for the background check.
+    pub fn fetch_wasm_pack_latest_version() -> Result<String> {
+        Self::return_api_call_result(chrono::offset::Local::now())
+    }
+
     fn return_api_call_result(current_time: DateTime<offset::Local>) -> Result<String> {
         let version = Self::return_latest_wasm_pack_version();
 
-        // We always override the stamp file with the current time because we don't
+        // We always override the stamps with the current time because we don't
         // want to hit the API all the time if it fails. It should follow the same
-        // "policy" as the success. This means that the 24 hours rate limiting
-        // will be active regardless if the check succeeded or failed.
-        match version {
-            Ok(ref version) => Self::override_stamp_file(current_time, Some(&version)).ok(),
-            Err(_) => Self::override_stamp_file(current_time, None).ok(),
-        };
-
-        version
-    }
-
-    fn override_stamp_file(
-        current_time: DateTime<offset::Local>,
-        version: Option<&str>,
-    ) -> Result<()> {
-        let path = env::current_exe()?;
-
-        let mut file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .append(true)
-            .create(true)
-            .open(path.with_extension("stamp"))?;
-
-        file.set_len(0)?;
-
-        write!(file, "created {:?}", current_time)?;
-
-        if let Some(version) = version {
-            write!(file, "\nversion {}", version)?;
-        }
-
-        Ok(())
-    }
-
-    /// Return stamp file where metadata is stored.
-    fn return_wasm_pack_file() -> Option<String> {
-        if let Ok(path) = env::current_exe() {
-            if let Ok(file) = fs::read_to_string(path.with_extension("stamp")) {
-                return Some(file);
+        // "policy" as the success. This means that the rate limiting of
+        // `update-check-interval` will be active regardless if the check
+        // succeeded or failed.
+        let saved = stamps::update_stamps(|stamps| {
+            stamps.insert(
+                stamps::WASM_PACK_LAST_CHECKED_STAMP.to_string(),
+                current_time.to_rfc3339().into(),
+            );
+            match &version {
+                Ok(version) => {
+                    stamps.insert(
+                        stamps::WASM_PACK_LATEST_VERSION_STAMP.to_string(),
+                        version.as_str().into(),
+                    );
+                }
+                Err(_) => {
+                    stamps.remove(stamps::WASM_PACK_LATEST_VERSION_STAMP);
+                }
             }
+        });
+        if let Err(e) = saved {
+            log::debug!("cannot save the latest wasm-pack version: {}", e);
         }
-        None
+
+        version
     }
 
     /// Returns wasm-pack latest version (if it's received) by executing check_wasm_pack_latest_version function.
@@ -223,19 +301,17 @@ impl Crate {
         Self::check_wasm_pack_latest_version().map(|crt| crt.crt.max_version)
     }
 
-    /// Read the stamp file and return value assigned to a certain key.
-    fn return_stamp_file_value(file: &str, word: &str) -> Option<String> {
-        let created = file
-            .lines()
-            .find(|line| line.starts_with(word))
-            .and_then(|l| l.split_whitespace().nth(1));
-
-        created.map(|s| s.to_string())
-    }
-
-    /// Call to the crates.io api and return the latest version of `wasm-pack`
+    /// Call to the crates.io api, or the configured `update-check-url`, and
+    /// return the latest version of `wasm-pack`
     fn check_wasm_pack_latest_version() -> Result<Crate> {
-        let url = "https://crates.io/api/v1/crates/wasm-pack";
+        let url = config::get().update_check_url().value;
+        if let Some(path) = download::file_url_path(&url) {
+            let json = fs::read_to_string(&path)
+                .with_context(|| anyhow!("failed to read {}", path.display()))?;
+            return serde_json::from_str(&json)
+                .with_context(|| anyhow!("{} is not a crates.io API response", path.display()));
+        }
+
         let agent = ureq::builder()
             .try_proxy_from_env(true)
             .user_agent(&format!(
@@ -245,7 +321,7 @@ impl Crate {
             ))
             .build();
         let resp = agent
-            .get(url)
+            .get(&url)
             .call()
             .context("failed to get wasm-pack version")?;
 
@@ -289,6 +365,8 @@ impl CargoWasmPackProfile {
                 split_linked_modules: Some(false),
             },
             wasm_opt: None,
+            size_budget: None,
+            threads: Some(false),
         }
     }
 
@@ -302,6 +380,8 @@ impl CargoWasmPackProfile {
                 split_linked_modules: Some(false),
             },
             wasm_opt: Some(CargoWasmPackProfileWasmOpt::Enabled(true)),
+            size_budget: None,
+            threads: Some(false),
         }
     }
 
@@ -315,58 +395,11 @@ impl CargoWasmPackProfile {
                 split_linked_modules: Some(false),
             },
             wasm_opt: Some(CargoWasmPackProfileWasmOpt::Enabled(true)),
+            size_budget: None,
+            threads: Some(false),
         }
     }
 
-    fn default_custom() -> Self {
-        CargoWasmPackProfile {
-            wasm_bindgen: CargoWasmPackProfileWasmBindgen {
-                debug_js_glue: Some(false),
-                demangle_name_section: Some(true),
-                dwarf_debug_info: Some(false),
-                omit_default_module_path: Some(false),
-                split_linked_modules: Some(false),
-            },
-            wasm_opt: Some(CargoWasmPackProfileWasmOpt::Enabled(true)),
-        }
-    }
-
-    fn deserialize_dev<'de, D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mut profile = <Option<Self>>::deserialize(deserializer)?.unwrap_or_default();
-        profile.update_with_defaults(&Self::default_dev());
-        Ok(profile)
-    }
-
-    fn deserialize_release<'de, D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mut profile = <Option<Self>>::deserialize(deserializer)?.unwrap_or_default();
-        profile.update_with_defaults(&Self::default_release());
-        Ok(profile)
-    }
-
-    fn deserialize_profiling<'de, D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mut profile = <Option<Self>>::deserialize(deserializer)?.unwrap_or_default();
-        profile.update_with_defaults(&Self::default_profiling());
-        Ok(profile)
-    }
-
-    fn deserialize_custom<'de, D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mut profile = <Option<Self>>::deserialize(deserializer)?.unwrap_or_default();
-        profile.update_with_defaults(&Self::default_custom());
-        Ok(profile)
-    }
-
     fn update_with_defaults(&mut self, defaults: &Self) {
         macro_rules! d {
             ( $( $path:ident ).* ) => {
@@ -378,10 +411,46 @@ impl CargoWasmPackProfile {
         d!(wasm_bindgen.dwarf_debug_info);
         d!(wasm_bindgen.omit_default_module_path);
         d!(wasm_bindgen.split_linked_modules);
+        d!(threads);
 
         if self.wasm_opt.is_none() {
             self.wasm_opt = defaults.wasm_opt.clone();
         }
+        if self.size_budget.is_none() {
+            self.size_budget = defaults.size_budget.clone();
+        }
+    }
+
+    /// Resolve the profile called `name` from the `tables` in `Cargo.toml`.
+    ///
+    /// Settings missing from a profile's table are taken from the profile it
+    /// inherits from in cargo's `[profile.*]` tables, and ultimately from the
+    /// defaults of `dev`, `release` or `profiling`. `seen` guards against
+    /// cycles in the `inherits` chain.
+    fn resolve(
+        name: &str,
+        tables: &BTreeMap<String, CargoWasmPackProfile>,
+        inherits: &HashMap<String, String>,
+        seen: &mut Vec<String>,
+    ) -> Self {
+        let (table, defaults) = match name {
+            "dev" => (tables.get(name), Self::default_dev()),
+            "release" => (tables.get(name), Self::default_release()),
+            "profiling" => (tables.get(name), Self::default_profiling()),
+            _ => {
+                seen.push(name.to_string());
+                let defaults = match inherits.get(name) {
+                    Some(parent) if !seen.contains(parent) => {
+                        Self::resolve(parent, tables, inherits, seen)
+                    }
+                    _ => Self::default_release(),
+                };
+                (tables.get(name), defaults)
+            }
+        };
+        let mut profile = table.cloned().unwrap_or_default();
+        profile.update_with_defaults(&defaults);
+        profile
     }
 
     /// Get this profile's configured `[wasm-bindgen.debug-js-glue]` value.
@@ -417,6 +486,16 @@ impl CargoWasmPackProfile {
             CargoWasmPackProfileWasmOpt::ExplicitArgs(s) => Some(s.clone()),
         }
     }
+
+    /// Get this profile's configured `threads` value.
+    pub fn threads(&self) -> bool {
+        self.threads.unwrap()
+    }
+
+    /// Get this profile's configured `[size-budget]`, if any.
+    pub fn size_budget(&self) -> Option<&SizeBudget> {
+        self.size_budget.as_ref()
+    }
 }
 
 struct NpmData {
@@ -451,7 +530,81 @@ impl CrateData {
             .manifest_path(&manifest_path)
             .exec()?;
 
-        let manifest_and_keys = CrateData::parse_crate_data(&manifest_path)?;
+        CrateData::from_metadata(data, &manifest_path, out_name)
+    }
+
+    /// Reads metadata for every member of the workspace containing the crate
+    /// (or virtual manifest) inside the directory specified by `path` that has
+    /// a `cdylib` target.
+    ///
+    /// If `packages` is not empty, only the members with those names are
+    /// returned, and it is an error for any of them to be missing.
+    pub fn workspace_members(
+        crate_path: &Path,
+        packages: &[String],
+        out_name: Option<String>,
+    ) -> Result<Vec<CrateData>> {
+        let manifest_path = crate_path.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            bail!(
+                "crate directory is missing a `Cargo.toml` file; is `{}` the \
+                 wrong directory?",
+                crate_path.display()
+            )
+        }
+
+        let data = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&manifest_path)
+            .exec()?;
+
+        for package in packages {
+            let member = data
+                .workspace_members
+                .iter()
+                .map(|id| &data[id])
+                .find(|pkg| pkg.name == *package);
+            match member {
+                Some(pkg) if CrateData::has_cdylib(pkg) => {}
+                Some(_) => bail!(
+                    "workspace member `{}` does not have a `cdylib` target",
+                    package
+                ),
+                None => bail!("`{}` is not a member of the workspace", package),
+            }
+        }
+
+        let members = data
+            .workspace_members
+            .iter()
+            .map(|id| &data[id])
+            .filter(|pkg| CrateData::has_cdylib(pkg))
+            .filter(|pkg| packages.is_empty() || packages.contains(&pkg.name))
+            .map(|pkg| pkg.manifest_path.clone().into_std_path_buf())
+            .collect::<Vec<_>>();
+        if members.is_empty() {
+            bail!(
+                "no workspace members with a `cdylib` target were found in {}",
+                data.workspace_root
+            )
+        }
+
+        members
+            .iter()
+            .map(|manifest_path| {
+                CrateData::from_metadata(data.clone(), manifest_path, out_name.clone())
+            })
+            .collect()
+    }
+
+    fn from_metadata(
+        data: Metadata,
+        manifest_path: &Path,
+        out_name: Option<String>,
+    ) -> Result<CrateData> {
+        let mut manifest_and_keys = CrateData::parse_crate_data(manifest_path)?;
+        let mut workspace_wasm_pack =
+            CrateData::parse_workspace_metadata(&data, &mut manifest_and_keys.unused_keys)?;
+        workspace_wasm_pack.resolve_paths(data.workspace_root.as_std_path());
         CrateData::warn_for_unused_keys(&manifest_and_keys);
 
         let manifest = manifest_and_keys.manifest;
@@ -460,18 +613,50 @@ impl CrateData {
             .iter()
             .position(|pkg| {
                 pkg.name == manifest.package.name
-                    && CrateData::is_same_path(pkg.manifest_path.as_std_path(), &manifest_path)
+                    && CrateData::is_same_path(pkg.manifest_path.as_std_path(), manifest_path)
             })
             .ok_or_else(|| anyhow!("failed to find package in metadata"))?;
 
+        let mut package_wasm_pack = manifest.package.metadata.wasm_pack;
+        package_wasm_pack.resolve_paths(manifest_path.parent().unwrap_or(Path::new(".")));
+        let mut build_defaults = package_wasm_pack.or(workspace_wasm_pack);
+        let tables = std::mem::take(&mut build_defaults.profile);
+        let profiles = CrateData::resolve_profiles(&data, &tables);
+        let profile_tables = tables.into_keys().collect();
+        let out_name = out_name.or_else(|| build_defaults.out_name.clone());
+
         Ok(CrateData {
             data,
-            manifest,
             current_idx,
             out_name,
+            profiles,
+            profile_tables,
+            build_defaults,
         })
     }
 
+    /// Deserialize `[workspace.metadata.wasm-pack]`, adding the keys that are
+    /// not understood to `unused_keys`.
+    fn parse_workspace_metadata(
+        data: &Metadata,
+        unused_keys: &mut BTreeSet<String>,
+    ) -> Result<CargoWasmPack> {
+        let metadata = match data.workspace_metadata.get("wasm-pack") {
+            Some(metadata) => metadata.clone(),
+            None => return Ok(CargoWasmPack::default()),
+        };
+        serde_ignored::deserialize(metadata, |path| {
+            unused_keys.insert(format!("{}.{}", WORKSPACE_WASM_PACK_METADATA_KEY, path));
+        })
+        .with_context(|| anyhow!("failed to parse `[{}]`", WORKSPACE_WASM_PACK_METADATA_KEY))
+    }
+
+    /// The project's defaults for the options of `wasm-pack build`, from
+    /// `[package.metadata.wasm-pack]` and `[workspace.metadata.wasm-pack]`.
+    pub fn build_defaults(&self) -> &CargoWasmPack {
+        &self.build_defaults
+    }
+
     fn is_same_path(path1: &Path, path2: &Path) -> bool {
         if let Ok(path1) = fs::canonicalize(&path1) {
             if let Ok(path2) = fs::canonicalize(&path2) {
@@ -525,13 +710,91 @@ impl CrateData {
         });
     }
 
+    /// Resolve the wasm-pack configuration of every profile that is either
+    /// configured in `[package.metadata.wasm-pack.profile.<name>]` or defined
+    /// by cargo, following cargo's `inherits` chains.
+    fn resolve_profiles(
+        data: &Metadata,
+        tables: &BTreeMap<String, CargoWasmPackProfile>,
+    ) -> HashMap<String, CargoWasmPackProfile> {
+        // Cargo only reads profiles from the workspace root's manifest, and
+        // has already validated it, so anything unreadable is just skipped.
+        let cargo_profiles = fs::read_to_string(data.workspace_root.join("Cargo.toml"))
+            .ok()
+            .and_then(|contents| toml::from_str::<CargoProfiles>(&contents).ok())
+            .unwrap_or_default();
+        let mut inherits: HashMap<String, String> = [("test", "dev"), ("bench", "release")]
+            .iter()
+            .map(|(name, parent)| (name.to_string(), parent.to_string()))
+            .collect();
+        for name in tables.keys() {
+            let known = ["dev", "release", "profiling", "test", "bench"];
+            if !known.contains(&name.as_str())
+                && name != LEGACY_CUSTOM_PROFILE
+                && !cargo_profiles.profile.contains_key(name)
+            {
+                PBAR.warn(&format!(
+                    "\"profile.{}\" is not a cargo profile and will be ignored. \
+                     Please check your Cargo.toml.",
+                    name
+                ));
+            }
+        }
+        for (name, profile) in cargo_profiles.profile {
+            if let Some(parent) = profile.inherits {
+                inherits.insert(name, parent);
+            }
+        }
+
+        let names: BTreeSet<&str> = ["dev", "release", "profiling", LEGACY_CUSTOM_PROFILE]
+            .into_iter()
+            .chain(tables.keys().map(|name| name.as_str()))
+            .chain(inherits.keys().map(|name| name.as_str()))
+            .collect();
+        names
+            .into_iter()
+            .map(|name| {
+                let profile =
+                    CargoWasmPackProfile::resolve(name, tables, &inherits, &mut Vec::new());
+                (name.to_string(), profile)
+            })
+            .collect()
+    }
+
     /// Get the configured profile.
+    ///
+    /// Custom profiles are looked up by name, falling back along cargo's
+    /// `inherits` chain when they have no table of their own. Cargo rejects
+    /// profiles it doesn't define, so any others get `release`'s settings.
     pub fn configured_profile(&self, profile: BuildProfile) -> &CargoWasmPackProfile {
-        match profile {
-            BuildProfile::Dev => &self.manifest.package.metadata.wasm_pack.profile.dev,
-            BuildProfile::Profiling => &self.manifest.package.metadata.wasm_pack.profile.profiling,
-            BuildProfile::Release => &self.manifest.package.metadata.wasm_pack.profile.release,
-            BuildProfile::Custom(_) => &self.manifest.package.metadata.wasm_pack.profile.custom,
+        let name = match &profile {
+            BuildProfile::Dev => "dev",
+            BuildProfile::Profiling => "profiling",
+            BuildProfile::Release => "release",
+            BuildProfile::Custom(name) => name.as_str(),
+        };
+        self.profiles
+            .get(name)
+            .unwrap_or_else(|| &self.profiles["release"])
+    }
+
+    /// Warn when building the custom `profile` ignores the legacy `custom`
+    /// table, which older versions of wasm-pack used for every custom profile
+    /// without a table of its own.
+    pub fn warn_for_legacy_custom_profile(&self, profile: &BuildProfile) {
+        let name = match profile {
+            BuildProfile::Custom(name) => name,
+            _ => return,
+        };
+        if name != LEGACY_CUSTOM_PROFILE
+            && self.profile_tables.contains(LEGACY_CUSTOM_PROFILE)
+            && !self.profile_tables.contains(name)
+        {
+            PBAR.warn(&format!(
+                "[package.metadata.wasm-pack.profile.{}] is no longer used for `--profile {}`. \
+                 Rename it to [package.metadata.wasm-pack.profile.{}] to keep its settings.",
+                LEGACY_CUSTOM_PROFILE, name, name
+            ));
         }
     }
 
@@ -542,13 +805,7 @@ impl CrateData {
     }
 
     fn check_crate_type(&self) -> Result<()> {
-        let pkg = &self.data.packages[self.current_idx];
-        let any_cdylib = pkg
-            .targets
-            .iter()
-            .filter(|target| target.kind.iter().any(|k| k == "cdylib"))
-            .any(|target| target.crate_types.iter().any(|s| s == "cdylib"));
-        if any_cdylib {
+        if CrateData::has_cdylib(self.pkg()) {
             return Ok(());
         }
         bail!(
@@ -559,10 +816,37 @@ impl CrateData {
         )
     }
 
+    fn has_cdylib(pkg: &cargo_metadata::Package) -> bool {
+        pkg.targets
+            .iter()
+            .filter(|target| target.kind.iter().any(|k| k == "cdylib"))
+            .any(|target| target.crate_types.iter().any(|s| s == "cdylib"))
+    }
+
     fn pkg(&self) -> &cargo_metadata::Package {
         &self.data.packages[self.current_idx]
     }
 
+    /// Get the id of the package, as used by cargo in `cargo metadata` and
+    /// its build messages.
+    pub fn package_id(&self) -> &cargo_metadata::PackageId {
+        &self.pkg().id
+    }
+
+    /// Get the package name, as given in its Cargo.toml.
+    pub fn package_name(&self) -> &str {
+        &self.pkg().name
+    }
+
+    /// Returns the path to the directory containing this crate's Cargo.toml.
+    pub fn manifest_directory(&self) -> &Path {
+        self.pkg()
+            .manifest_path
+            .parent()
+            .map(|dir| dir.as_std_path())
+            .unwrap_or_else(|| Path::new("."))
+    }
+
     /// Get the crate name for the crate at the given path.
     pub fn crate_name(&self) -> String {
         let pkg = self.pkg();
@@ -611,37 +895,71 @@ impl CrateData {
         Path::new(&self.data.target_directory)
     }
 
+    /// Returns the directories of this crate and of every crate it depends on
+    /// through a `path` dependency, directly or transitively.
+    pub fn path_dependency_directories(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.manifest_directory().to_path_buf()];
+        let mut pending = vec![self.pkg()];
+        while let Some(pkg) = pending.pop() {
+            for dep in &pkg.dependencies {
+                let dep_dir = match &dep.path {
+                    Some(path) => path.as_std_path(),
+                    None => continue,
+                };
+                if dirs.iter().any(|dir| CrateData::is_same_path(dir, dep_dir)) {
+                    continue;
+                }
+                dirs.push(dep_dir.to_path_buf());
+                let dep_pkg = self.data.packages.iter().find(|p| {
+                    p.manifest_path
+                        .parent()
+                        .is_some_and(|dir| CrateData::is_same_path(dir.as_std_path(), dep_dir))
+                });
+                if let Some(dep_pkg) = dep_pkg {
+                    pending.push(dep_pkg);
+                }
+            }
+        }
+        dirs
+    }
+
     /// Returns the path to this project's root cargo workspace directory
     pub fn workspace_root(&self) -> &Path {
         Path::new(&self.data.workspace_root)
     }
 
     /// Generate a package.json file inside in `./pkg`.
+    ///
+    /// When more than one target is given, each target's bindings are
+    /// expected in a sub-directory of `out_dir` named after the target, and a
+    /// single package.json with a conditional `exports` map is written.
     pub fn write_package_json(
         &self,
         out_dir: &Path,
         scope: &Option<String>,
         disable_dts: bool,
-        target: Target,
+        targets: &[Target],
     ) -> Result<()> {
         let pkg_file_path = out_dir.join("package.json");
-        // Check if a `package.json` was already generated by wasm-bindgen, if so
-        // we merge the NPM dependencies already specified in it.
-        let existing_deps = if pkg_file_path.exists() {
-            // It's just a map of dependency names to versions
-            Some(serde_json::from_str::<HashMap<String, String>>(
-                &fs::read_to_string(&pkg_file_path)?,
-            )?)
-        } else {
-            None
-        };
-        let npm_data = match target {
-            Target::Nodejs => self.to_commonjs(scope, disable_dts, existing_deps, out_dir),
-            Target::NoModules => self.to_nomodules(scope, disable_dts, existing_deps, out_dir),
-            Target::Bundler => self.to_esmodules(scope, disable_dts, existing_deps, out_dir),
-            Target::Web => self.to_web(scope, disable_dts, existing_deps, out_dir),
-            // Deno does not need package.json
-            Target::Deno => return Ok(()),
+        let npm_data = match targets {
+            [target] => {
+                // Check if a `package.json` was already generated by wasm-bindgen, if so
+                // we merge the NPM dependencies already specified in it.
+                let existing_deps = CrateData::read_wasm_bindgen_deps(&pkg_file_path)?;
+                match target {
+                    Target::Nodejs => self.to_commonjs(scope, disable_dts, existing_deps, out_dir),
+                    Target::NoModules => {
+                        self.to_nomodules(scope, disable_dts, existing_deps, out_dir)
+                    }
+                    Target::Bundler => {
+                        self.to_esmodules(scope, disable_dts, existing_deps, out_dir)
+                    }
+                    Target::Web => self.to_web(scope, disable_dts, existing_deps, out_dir),
+                    // Deno does not need package.json
+                    Target::Deno => return Ok(()),
+                }
+            }
+            targets => self.to_multi_target(scope, disable_dts, targets, out_dir)?,
         };
 
         let npm_json = serde_json::to_string_pretty(&npm_data)?;
@@ -651,6 +969,20 @@ impl CrateData {
         Ok(())
     }
 
+    /// Read the NPM dependencies that wasm-bindgen wrote to `pkg_file_path`,
+    /// if any.
+    pub(crate) fn read_wasm_bindgen_deps(
+        pkg_file_path: &Path,
+    ) -> Result<Option<HashMap<String, String>>> {
+        if !pkg_file_path.exists() {
+            return Ok(None);
+        }
+        // It's just a map of dependency names to versions
+        let deps = serde_json::from_str(&fs::read_to_string(pkg_file_path)?)
+            .with_context(|| anyhow!("failed to parse: {}", pkg_file_path.display()))?;
+        Ok(Some(deps))
+    }
+
     fn npm_data(
         &self,
         scope: &Option<String>,
@@ -689,17 +1021,7 @@ impl CrateData {
             None
         };
 
-        if let Ok(entries) = fs::read_dir(out_dir) {
-            let file_names = entries
-                .filter_map(|e| e.ok())
-                .filter(|e| e.metadata().map(|m| m.is_file()).unwrap_or(false))
-                .filter_map(|e| e.file_name().into_string().ok())
-                .filter(|f| f.starts_with("LICENSE"))
-                .filter(|f| f != "LICENSE");
-            for file_name in file_names {
-                files.push(file_name);
-            }
-        }
+        files.extend(CrateData::license_files(out_dir));
 
         NpmData {
             name: npm_name,
@@ -711,6 +1033,21 @@ impl CrateData {
         }
     }
 
+    /// Extra `LICENSE-*` files copied into `out_dir`, which npm doesn't pick
+    /// up on its own.
+    fn license_files(out_dir: &Path) -> Vec<String> {
+        match fs::read_dir(out_dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.metadata().map(|m| m.is_file()).unwrap_or(false))
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter(|f| f.starts_with("LICENSE"))
+                .filter(|f| f != "LICENSE")
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
     fn license(&self) -> Option<String> {
         self.crate_license().clone().or_else(|| {
             self.crate_license_file().clone().map(|file| {
@@ -848,6 +1185,116 @@ impl CrateData {
         })
     }
 
+    fn to_multi_target(
+        &self,
+        scope: &Option<String>,
+        disable_dts: bool,
+        targets: &[Target],
+        out_dir: &Path,
+    ) -> Result<NpmPackage> {
+        let data = self.npm_data(scope, false, disable_dts, out_dir);
+        let pkg = &self.data.packages[self.current_idx];
+        let entry = |target: &Target| format!("./{}/{}", target, data.main);
+
+        let mut files = Vec::new();
+        let mut dependencies: Option<HashMap<String, String>> = None;
+        let mut exports = ExportConditions::default();
+        let mut side_effects = Vec::new();
+
+        for target in targets {
+            let target_dir = out_dir.join(target.to_string());
+            files.push(format!("{}/", target));
+
+            // Each target directory may carry its own NPM dependencies written
+            // by wasm-bindgen; they all end up in the top-level package.json.
+            let pkg_file_path = target_dir.join("package.json");
+            if let Some(deps) = CrateData::read_wasm_bindgen_deps(&pkg_file_path)? {
+                dependencies.get_or_insert_with(HashMap::new).extend(deps);
+            }
+
+            // Tell Node.js how to load the `.js` files in this directory, since
+            // the top-level package mixes CommonJS and ES modules.
+            let module_type = match target {
+                Target::Nodejs => Some("commonjs"),
+                Target::Bundler | Target::Web | Target::Deno => Some("module"),
+                Target::NoModules => None,
+            };
+            match module_type {
+                Some(ty) => {
+                    let marker = serde_json::to_string_pretty(&serde_json::json!({ "type": ty }))?;
+                    fs::write(&pkg_file_path, marker)
+                        .with_context(|| anyhow!("failed to write: {}", pkg_file_path.display()))?;
+                }
+                None => {
+                    let _ = fs::remove_file(&pkg_file_path);
+                }
+            }
+
+            match target {
+                Target::Bundler => {
+                    exports.import.get_or_insert_with(|| entry(target));
+                    side_effects.push(entry(target));
+                }
+                Target::Nodejs => {
+                    exports.node.get_or_insert_with(|| entry(target));
+                    exports.require.get_or_insert_with(|| entry(target));
+                }
+                Target::Web => {
+                    exports.browser.get_or_insert_with(|| entry(target));
+                }
+                Target::NoModules | Target::Deno => {}
+            }
+            // Resolvers that match none of the other conditions get the first
+            // target that has one.
+            if matches!(target, Target::Bundler | Target::Nodejs | Target::Web) {
+                exports.default.get_or_insert_with(|| entry(target));
+            }
+            side_effects.push(format!("./{}/snippets/*", target));
+        }
+        files.extend(CrateData::license_files(out_dir));
+
+        // The first target given that a package is usually consumed as provides
+        // the type declarations and the fallback `main`, rather than a
+        // `no-modules` or `deno` build.
+        let primary = targets
+            .iter()
+            .find(|target| matches!(target, Target::Bundler | Target::Nodejs | Target::Web))
+            .unwrap_or(&targets[0]);
+        let types = data
+            .dts_file
+            .as_ref()
+            .map(|dts| format!("./{}/{}", primary, dts));
+        exports.types = types.clone();
+
+        let main = exports.require.clone().unwrap_or_else(|| entry(primary));
+
+        self.check_optional_fields();
+
+        Ok(NpmPackage::MultiTargetPackage(MultiTargetPackage {
+            name: data.name,
+            collaborators: pkg.authors.clone(),
+            description: self.pkg().description.clone(),
+            version: pkg.version.to_string(),
+            license: self.license(),
+            repository: self.pkg().repository.clone().map(|repo_url| Repository {
+                ty: "git".to_string(),
+                url: repo_url,
+            }),
+            files,
+            main,
+            module: exports.import.clone(),
+            homepage: data.homepage,
+            types,
+            exports: Exports {
+                root: exports,
+                package_json: "./package.json".to_string(),
+            },
+            side_effects,
+            keywords: data.keywords,
+            dependencies,
+        }))
+    }
+
     fn check_optional_fields(&self) {
         let mut messages = vec![];
         if self.pkg().description.is_none() {
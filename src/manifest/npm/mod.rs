@@ -1,10 +1,12 @@
 mod commonjs;
 mod esmodules;
+mod multitarget;
 mod nomodules;
 pub mod repository;
 
 pub use self::commonjs::CommonJSPackage;
 pub use self::esmodules::ESModulesPackage;
+pub use self::multitarget::{ExportConditions, Exports, MultiTargetPackage};
 pub use self::nomodules::NoModulesPackage;
 
 #[derive(Serialize)]
@@ -13,4 +15,5 @@ pub enum NpmPackage {
     CommonJSPackage(CommonJSPackage),
     ESModulesPackage(ESModulesPackage),
     NoModulesPackage(NoModulesPackage),
+    MultiTargetPackage(MultiTargetPackage),
 }
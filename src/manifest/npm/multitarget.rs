@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use crate::manifest::npm::repository::Repository;
+
+#[derive(Serialize)]
+pub struct MultiTargetPackage {
+    pub name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub collaborators: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<Repository>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    pub main: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<String>,
+    pub exports: Exports,
+    #[serde(rename = "sideEffects")]
+    pub side_effects: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keywords: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<HashMap<String, String>>,
+}
+
+#[derive(Serialize)]
+pub struct Exports {
+    #[serde(rename = ".")]
+    pub root: ExportConditions,
+    #[serde(rename = "./package.json")]
+    pub package_json: String,
+}
+
+/// Conditional exports, in the order in which resolvers should try them.
+/// Resolvers pick the first condition that matches, so `node` comes before
+/// `import`, which Node.js also matches. Bundlers match both `import` and
+/// `browser`, and get the `bundler` output from `import`, while `browser` is
+/// left to the `web` output for resolvers that don't bundle.
+#[derive(Default, Serialize)]
+pub struct ExportConditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
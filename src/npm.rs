@@ -2,15 +2,22 @@
 
 use crate::child;
 use crate::command::publish::access::Access;
+use crate::config;
 use anyhow::{bail, Context, Result};
 use log::info;
+use std::process::Command;
 
 /// The default npm registry used when we aren't working with a custom registry.
 pub const DEFAULT_NPM_REGISTRY: &str = "https://registry.npmjs.org/";
 
+/// A command running the configured npm client, `npm` by default.
+fn npm_command() -> Command {
+    child::new_command(&config::get().npm_client().value)
+}
+
 /// Run the `npm pack` command.
 pub fn npm_pack(path: &str) -> Result<()> {
-    let mut cmd = child::new_command("npm");
+    let mut cmd = npm_command();
     cmd.current_dir(path).arg("pack");
     child::run(cmd, "npm pack").context("Packaging up your code failed")?;
     Ok(())
@@ -18,7 +25,7 @@ pub fn npm_pack(path: &str) -> Result<()> {
 
 /// Run the `npm publish` command.
 pub fn npm_publish(path: &str, access: Option<Access>, tag: Option<String>) -> Result<()> {
-    let mut cmd = child::new_command("npm");
+    let mut cmd = npm_command();
     match access {
         Some(a) => cmd.current_dir(path).arg("publish").arg(&a.to_string()),
         None => cmd.current_dir(path).arg("publish"),
@@ -45,11 +52,11 @@ pub fn npm_login(registry: &str, scope: &Option<String>, auth_type: &Option<Stri
 
     // Interactively ask user for npm login info.
     //  (child::run does not support interactive input)
-    let mut cmd = child::new_command("npm");
+    let mut cmd = npm_command();
     cmd.args(args);
 
     info!("Running {:?}", cmd);
-    if cmd.status()?.success() {
+    if child::status(&mut cmd)?.success() {
         Ok(())
     } else {
         bail!("Login to registry {} failed", registry)
@@ -1,9 +1,15 @@
 //! Fancy progress bar functionality.
 
+use crate::cache::Entry;
+use crate::config::ListedSetting;
+use crate::doctor::Check;
 use crate::emoji;
+use crate::size::{FileSize, ModuleSections};
 use anyhow::{bail, Error, Result};
 use console::style;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::Duration;
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
@@ -31,10 +37,157 @@ impl std::str::FromStr for LogLevel {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The format that wasm-pack prints its messages in
+pub enum MessageFormat {
+    /// Styled text on stderr, for people to read
+    Human,
+    /// One JSON object per line on stdout, for tools to parse
+    Json,
+}
+
+impl std::str::FromStr for MessageFormat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => bail!("Unknown message-format: {}", s),
+        }
+    }
+}
+
+/// An event printed when running with `--message-format json`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    /// A step of a command has started.
+    StepStarted {
+        /// The name of the step.
+        name: &'a str,
+    },
+    /// A step of a command has finished, successfully or not.
+    StepFinished {
+        /// The name of the step.
+        name: &'a str,
+        /// How long the step took, in milliseconds.
+        duration_ms: u64,
+        /// Whether the step succeeded.
+        success: bool,
+    },
+    /// An informational message.
+    Info {
+        /// The message.
+        message: &'a str,
+    },
+    /// A warning.
+    Warning {
+        /// The warning.
+        message: &'a str,
+    },
+    /// An error that didn't necessarily stop the command.
+    Error {
+        /// The error.
+        message: &'a str,
+    },
+    /// An external tool was found or installed.
+    ToolResolved {
+        /// The name of the tool.
+        tool: &'a str,
+        /// The path to the tool's binary.
+        path: &'a Path,
+        /// The version of the tool, if known.
+        version: Option<&'a str>,
+    },
+    /// A file was written to the output directory.
+    OutputWritten {
+        /// The path of the file.
+        path: &'a Path,
+        /// The size of the file, in bytes.
+        size: u64,
+    },
+    /// The sizes of the files generated into an output directory.
+    SizeReport {
+        /// The output directory.
+        path: &'a Path,
+        /// The size of every `.wasm` and `.js` file.
+        files: &'a [FileSize],
+        /// The sections of every `.wasm` file.
+        modules: &'a [ModuleSections],
+    },
+    /// The results of `wasm-pack doctor`.
+    DoctorReport {
+        /// Every check, in the order they were run.
+        checks: &'a [Check],
+    },
+    /// The settings listed by `wasm-pack config list`.
+    ConfigList {
+        /// Every setting that has a value, with its `key`, its `value`
+        /// formatted as TOML, and its `source`.
+        settings: &'a [ListedSetting],
+    },
+    /// The directory of the cache, printed by `wasm-pack cache path`.
+    CachePath {
+        /// The directory.
+        path: &'a Path,
+    },
+    /// The cached tools listed by `wasm-pack cache list`.
+    CacheList {
+        /// Every entry of the cache.
+        entries: &'a [Entry],
+    },
+    /// The result of `wasm-pack self check-update`.
+    UpdateCheck {
+        /// The version of this wasm-pack.
+        local: &'a str,
+        /// The latest released version of wasm-pack.
+        latest: &'a str,
+        /// Whether the latest version is newer than this one.
+        outdated: bool,
+    },
+    /// The command has finished.
+    Summary {
+        /// Whether the command succeeded.
+        success: bool,
+        /// How long the command took, in milliseconds.
+        duration_ms: u64,
+        /// The errors that made the command fail.
+        errors: Vec<ErrorMessage>,
+    },
+}
+
+/// An error in an `Event`, along with the errors that caused it.
+#[derive(Debug, Serialize)]
+pub struct ErrorMessage {
+    /// The error.
+    pub message: String,
+    /// The chain of underlying causes, outermost first.
+    pub causes: Vec<String>,
+}
+
+impl From<&Error> for ErrorMessage {
+    fn from(error: &Error) -> Self {
+        ErrorMessage {
+            message: error.to_string(),
+            causes: error
+                .chain()
+                .skip(1)
+                .map(|cause| cause.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Convert a `Duration` to whole milliseconds, for use in an `Event`.
+pub fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
 /// Synchronized progress bar and status message printing.
 pub struct ProgressOutput {
     quiet: AtomicBool,
     log_level: AtomicU8,
+    json: AtomicBool,
 }
 
 impl ProgressOutput {
@@ -43,6 +196,7 @@ impl ProgressOutput {
         Self {
             quiet: AtomicBool::new(false),
             log_level: AtomicU8::new(LogLevel::Info as u8),
+            json: AtomicBool::new(false),
         }
     }
 
@@ -71,9 +225,33 @@ impl ProgressOutput {
         self.log_level.store(log_level as u8, Ordering::SeqCst);
     }
 
+    /// Returns whether messages are printed as JSON events
+    pub fn is_json(&self) -> bool {
+        self.json.load(Ordering::SeqCst)
+    }
+
+    /// Sets the format that messages are printed in
+    pub fn set_message_format(&self, message_format: MessageFormat) {
+        self.json
+            .store(message_format == MessageFormat::Json, Ordering::SeqCst);
+    }
+
+    /// Print an event, if running with `--message-format json`.
+    pub fn event(&self, event: &Event) {
+        if self.is_json() {
+            match serde_json::to_string(event) {
+                Ok(json) => println!("{}", json),
+                Err(e) => log::warn!("failed to serialize {:?}: {}", event, e),
+            }
+        }
+    }
+
     /// Add an informational message.
     pub fn info(&self, message: &str) {
         if !self.quiet() && self.is_log_enabled(LogLevel::Info) {
+            if self.is_json() {
+                return self.event(&Event::Info { message });
+            }
             let info = format!("{}: {}", style("[INFO]").bold().dim(), message,);
             self.message(&info);
         }
@@ -82,6 +260,9 @@ impl ProgressOutput {
     /// Add a warning message.
     pub fn warn(&self, message: &str) {
         if !self.quiet() && self.is_log_enabled(LogLevel::Warn) {
+            if self.is_json() {
+                return self.event(&Event::Warning { message });
+            }
             let warn = format!(
                 "{}: {} {}",
                 style("[WARN]").bold().dim(),
@@ -95,6 +276,9 @@ impl ProgressOutput {
     /// Add an error message.
     pub fn error(&self, message: &str) {
         if self.is_log_enabled(LogLevel::Error) {
+            if self.is_json() {
+                return self.event(&Event::Error { message });
+            }
             let err = format!(
                 "{}: {} {}",
                 style("[ERR]").bold().dim(),
@@ -0,0 +1,809 @@
+//! Reporting the size of the generated package, and checking it against the
+//! size budget configured in `Cargo.toml`.
+
+use anyhow::{bail, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// The brotli quality of `wasm-pack size` and of size budgets, which is what
+/// servers use for static files.
+const BROTLI_BEST: u32 = 11;
+/// The brotli quality of the report printed after every build, which is much
+/// faster on large debug builds and only a few percent larger.
+const BROTLI_FAST: u32 = 5;
+
+/// The sizes of one generated file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSize {
+    /// The path of the file, relative to the output directory.
+    pub path: PathBuf,
+    /// The size of the file as written.
+    pub raw: u64,
+    /// The size of the file after gzip compression.
+    pub gzip: u64,
+    /// The size of the file after brotli compression.
+    pub brotli: u64,
+}
+
+impl FileSize {
+    /// Measure the file at `path`, naming it `relative` in the report.
+    pub fn measure(path: &Path, relative: PathBuf) -> Result<FileSize> {
+        FileSize::measure_with(path, relative, BROTLI_BEST)
+    }
+
+    fn measure_with(path: &Path, relative: PathBuf, brotli_quality: u32) -> Result<FileSize> {
+        let contents =
+            fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(FileSize {
+            path: relative,
+            raw: contents.len() as u64,
+            gzip: gzip_size(&contents)?,
+            brotli: brotli_size(&contents, brotli_quality)?,
+        })
+    }
+
+    fn is_wasm(&self) -> bool {
+        self.path.extension().is_some_and(|ext| ext == "wasm")
+    }
+}
+
+fn gzip_size(contents: &[u8]) -> Result<u64> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(contents)?;
+    Ok(encoder.finish()?.len() as u64)
+}
+
+fn brotli_size(contents: &[u8], quality: u32) -> Result<u64> {
+    let mut compressed = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, quality, 22);
+        encoder.write_all(contents)?;
+    }
+    Ok(compressed.len() as u64)
+}
+
+/// The size of one section of a wasm module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Section {
+    /// The kind of section, such as `code` or `data`, or `custom "<name>"`
+    /// for custom sections.
+    pub name: String,
+    /// The size of the section, including its header.
+    pub size: u64,
+}
+
+/// A section of a wasm module, as found while parsing it.
+struct RawSection<'a> {
+    id: u8,
+    /// The name of a custom section.
+    custom_name: Option<String>,
+    /// The section's contents, after the name for custom sections.
+    payload: &'a [u8],
+    /// The size of the whole section, including its header.
+    size: u64,
+}
+
+impl RawSection<'_> {
+    fn kind(&self) -> String {
+        let kind = match self.id {
+            0 => return format!("custom {:?}", self.custom_name.as_deref().unwrap_or("")),
+            1 => "type",
+            2 => "import",
+            3 => "function",
+            4 => "table",
+            5 => "memory",
+            6 => "global",
+            7 => "export",
+            8 => "start",
+            9 => "element",
+            10 => "code",
+            11 => "data",
+            12 => "datacount",
+            13 => "tag",
+            id => return format!("unknown ({})", id),
+        };
+        kind.to_string()
+    }
+}
+
+fn raw_sections(wasm: &[u8]) -> Result<Vec<RawSection<'_>>> {
+    if wasm.len() < 8 || &wasm[..4] != b"\0asm" {
+        bail!("not a wasm module");
+    }
+    let mut sections = Vec::new();
+    let mut offset = 8;
+    while offset < wasm.len() {
+        let start = offset;
+        let id = wasm[offset];
+        offset += 1;
+        let len = read_u32(wasm, &mut offset)? as usize;
+        let mut payload = slice(wasm, offset, len)?;
+        offset += len;
+        let mut custom_name = None;
+        if id == 0 {
+            let mut cursor = 0;
+            custom_name = Some(read_name(payload, &mut cursor)?);
+            payload = &payload[cursor..];
+        }
+        sections.push(RawSection {
+            id,
+            custom_name,
+            payload,
+            size: (offset - start) as u64,
+        });
+    }
+    Ok(sections)
+}
+
+/// Split a wasm module into its sections.
+pub fn sections(wasm: &[u8]) -> Result<Vec<Section>> {
+    Ok(raw_sections(wasm)?
+        .iter()
+        .map(|section| Section {
+            name: section.kind(),
+            size: section.size,
+        })
+        .collect())
+}
+
+/// The size of each function body in a wasm module, by the name that the
+/// `name` custom section gives it. Functions sharing a name are added up.
+/// Returns `None` if the module has no function names.
+pub fn function_sizes(wasm: &[u8]) -> Result<Option<BTreeMap<String, u64>>> {
+    let sections = raw_sections(wasm)?;
+    let names = match sections
+        .iter()
+        .find(|section| section.id == 0 && section.custom_name.as_deref() == Some("name"))
+    {
+        Some(section) => function_names(section.payload)?,
+        None => return Ok(None),
+    };
+    if names.is_empty() {
+        return Ok(None);
+    }
+    let imported = match sections.iter().find(|section| section.id == 2) {
+        Some(section) => imported_functions(section.payload)?,
+        None => 0,
+    };
+
+    let mut sizes = BTreeMap::new();
+    if let Some(code) = sections.iter().find(|section| section.id == 10) {
+        let mut offset = 0;
+        let count = read_u32(code.payload, &mut offset)?;
+        for i in 0..count {
+            let start = offset;
+            let len = read_u32(code.payload, &mut offset)? as usize;
+            slice(code.payload, offset, len)?;
+            offset += len;
+            let index = imported + i;
+            let name = names
+                .get(&index)
+                .cloned()
+                .unwrap_or_else(|| format!("func[{}]", index));
+            *sizes.entry(name).or_insert(0) += (offset - start) as u64;
+        }
+    }
+    Ok(Some(sizes))
+}
+
+/// Read the function names subsection of the `name` custom section.
+fn function_names(payload: &[u8]) -> Result<HashMap<u32, String>> {
+    let mut names = HashMap::new();
+    let mut offset = 0;
+    while offset < payload.len() {
+        let id = payload[offset];
+        offset += 1;
+        let len = read_u32(payload, &mut offset)? as usize;
+        let subsection = slice(payload, offset, len)?;
+        offset += len;
+        if id != 1 {
+            continue;
+        }
+        let mut cursor = 0;
+        let count = read_u32(subsection, &mut cursor)?;
+        for _ in 0..count {
+            let index = read_u32(subsection, &mut cursor)?;
+            names.insert(index, read_name(subsection, &mut cursor)?);
+        }
+    }
+    Ok(names)
+}
+
+/// Count the imported functions, which come before the module's own
+/// functions in the function index space.
+fn imported_functions(payload: &[u8]) -> Result<u32> {
+    let mut functions = 0;
+    let mut offset = 0;
+    let count = read_u32(payload, &mut offset)?;
+    for _ in 0..count {
+        read_name(payload, &mut offset)?;
+        read_name(payload, &mut offset)?;
+        let kind = read_byte(payload, &mut offset)?;
+        match kind {
+            0 => {
+                read_u32(payload, &mut offset)?;
+                functions += 1;
+            }
+            1 => {
+                read_byte(payload, &mut offset)?;
+                read_limits(payload, &mut offset)?;
+            }
+            2 => read_limits(payload, &mut offset)?,
+            3 => {
+                read_byte(payload, &mut offset)?;
+                read_byte(payload, &mut offset)?;
+            }
+            4 => {
+                read_byte(payload, &mut offset)?;
+                read_u32(payload, &mut offset)?;
+            }
+            _ => bail!("unknown import kind {} in the wasm module", kind),
+        }
+    }
+    Ok(functions)
+}
+
+fn read_limits(bytes: &[u8], offset: &mut usize) -> Result<()> {
+    let flags = read_byte(bytes, offset)?;
+    read_leb128(bytes, offset)?;
+    if flags & 1 != 0 {
+        read_leb128(bytes, offset)?;
+    }
+    Ok(())
+}
+
+fn read_name(bytes: &[u8], offset: &mut usize) -> Result<String> {
+    let len = read_u32(bytes, offset)? as usize;
+    let name = slice(bytes, *offset, len)?;
+    *offset += len;
+    Ok(String::from_utf8_lossy(name).into_owned())
+}
+
+fn slice(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+        .context("unexpected end of the wasm module")
+}
+
+fn read_byte(bytes: &[u8], offset: &mut usize) -> Result<u8> {
+    let byte = *bytes
+        .get(*offset)
+        .context("unexpected end of the wasm module")?;
+    *offset += 1;
+    Ok(byte)
+}
+
+fn read_u32(bytes: &[u8], offset: &mut usize) -> Result<u32> {
+    u32::try_from(read_leb128(bytes, offset)?).context("integer out of range in the wasm module")
+}
+
+fn read_leb128(bytes: &[u8], offset: &mut usize) -> Result<u64> {
+    let mut result: u64 = 0;
+    for shift in (0..70).step_by(7) {
+        let byte = read_byte(bytes, offset)?;
+        result |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+    bail!("invalid LEB128 integer in the wasm module")
+}
+
+/// The sizes of the `.wasm` and `.js` files in an output directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeReport {
+    /// Every `.wasm` and `.js` file, sorted by path.
+    pub files: Vec<FileSize>,
+    /// The sections of each `.wasm` file.
+    pub modules: Vec<ModuleSections>,
+}
+
+/// The sections of one `.wasm` file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleSections {
+    /// The path of the file, relative to the output directory.
+    pub path: PathBuf,
+    /// The sections, in the order they appear in the module.
+    pub sections: Vec<Section>,
+}
+
+impl SizeReport {
+    /// Measure the `.wasm` and `.js` files in `out_dir`.
+    pub fn new(out_dir: &Path) -> Result<SizeReport> {
+        SizeReport::measure(out_dir, BROTLI_BEST)
+    }
+
+    /// Measure the `.wasm` and `.js` files in `out_dir` quickly, with a brotli
+    /// size that is a little larger than the one `new` measures.
+    pub fn quick(out_dir: &Path) -> Result<SizeReport> {
+        SizeReport::measure(out_dir, BROTLI_FAST)
+    }
+
+    fn measure(out_dir: &Path, brotli_quality: u32) -> Result<SizeReport> {
+        let mut files = Vec::new();
+        let mut modules = Vec::new();
+        let entries = WalkDir::new(out_dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file());
+        for entry in entries {
+            let is_measured = entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext == "wasm" || ext == "js");
+            if !is_measured {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(out_dir)?.to_path_buf();
+            let file = FileSize::measure_with(entry.path(), relative, brotli_quality)?;
+            if file.is_wasm() {
+                let wasm = fs::read(entry.path())?;
+                let sections = sections(&wasm)
+                    .with_context(|| format!("failed to parse {}", entry.path().display()))?;
+                modules.push(ModuleSections {
+                    path: file.path.clone(),
+                    sections,
+                });
+            }
+            files.push(file);
+        }
+        Ok(SizeReport { files, modules })
+    }
+
+    /// The total sizes of the `.wasm` files, or of the `.js` files.
+    pub fn total(&self, wasm: bool) -> (u64, u64, u64) {
+        self.files
+            .iter()
+            .filter(|file| file.is_wasm() == wasm)
+            .fold((0, 0, 0), |(raw, gzip, brotli), file| {
+                (raw + file.raw, gzip + file.gzip, brotli + file.brotli)
+            })
+    }
+
+    /// Render the report as a table.
+    pub fn table(&self) -> String {
+        let width = self
+            .files
+            .iter()
+            .map(|file| file.path.display().to_string().len())
+            .chain(Some("file".len()))
+            .max()
+            .unwrap_or_default();
+        let mut table = String::new();
+        let _ = writeln!(
+            table,
+            "  {:<width$}  {:>10}  {:>10}  {:>10}",
+            "file", "raw", "gzip", "brotli"
+        );
+        for file in &self.files {
+            let _ = writeln!(
+                table,
+                "  {:<width$}  {:>10}  {:>10}  {:>10}",
+                file.path.display(),
+                format_bytes(file.raw),
+                format_bytes(file.gzip),
+                format_bytes(file.brotli),
+            );
+        }
+        for module in &self.modules {
+            let _ = writeln!(table, "  sections of {}:", module.path.display());
+            for section in &module.sections {
+                let _ = writeln!(
+                    table,
+                    "    {:<30}  {:>10}",
+                    section.name,
+                    format_bytes(section.size)
+                );
+            }
+        }
+        table.trim_end().to_string()
+    }
+
+    /// Check the report against `budget`, returning a description of every
+    /// limit that is exceeded.
+    pub fn exceeded(&self, budget: &SizeBudget) -> Vec<String> {
+        let mut exceeded = Vec::new();
+        let (wasm_raw, wasm_gzip, wasm_brotli) = self.total(true);
+        let (js_raw, js_gzip, js_brotli) = self.total(false);
+        let checks = [
+            ("wasm", wasm_raw, budget.wasm),
+            ("wasm-gzip", wasm_gzip, budget.wasm_gzip),
+            ("wasm-brotli", wasm_brotli, budget.wasm_brotli),
+            ("js", js_raw, budget.js),
+            ("js-gzip", js_gzip, budget.js_gzip),
+            ("js-brotli", js_brotli, budget.js_brotli),
+        ];
+        for (name, size, limit) in checks {
+            if let Some(limit) = limit {
+                if size > limit.0 {
+                    exceeded.push(format!(
+                        "{} is {}, over the budget of {}",
+                        name,
+                        format_bytes(size),
+                        format_bytes(limit.0)
+                    ));
+                }
+            }
+        }
+        exceeded
+    }
+}
+
+/// Limits on the total size of the generated `.wasm` and `.js` files, set in
+/// `[package.metadata.wasm-pack.profile.<profile>.size-budget]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SizeBudget {
+    /// The limit on the raw size of the `.wasm` files.
+    #[serde(default)]
+    pub wasm: Option<ByteSize>,
+    /// The limit on the gzipped size of the `.wasm` files.
+    #[serde(default, rename = "wasm-gzip")]
+    pub wasm_gzip: Option<ByteSize>,
+    /// The limit on the brotli-compressed size of the `.wasm` files.
+    #[serde(default, rename = "wasm-brotli")]
+    pub wasm_brotli: Option<ByteSize>,
+    /// The limit on the raw size of the `.js` files.
+    #[serde(default)]
+    pub js: Option<ByteSize>,
+    /// The limit on the gzipped size of the `.js` files.
+    #[serde(default, rename = "js-gzip")]
+    pub js_gzip: Option<ByteSize>,
+    /// The limit on the brotli-compressed size of the `.js` files.
+    #[serde(default, rename = "js-brotli")]
+    pub js_brotli: Option<ByteSize>,
+}
+
+/// A number of bytes, written in `Cargo.toml` either as an integer or as a
+/// string with a unit, such as `"150 KiB"` or `"1.5MB"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteSize(pub u64);
+
+impl std::str::FromStr for ByteSize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: f64 = number
+            .parse()
+            .with_context(|| format!("invalid size `{}`", s))?;
+        let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "b" => 1.0,
+            "kb" => 1e3,
+            "kib" => 1024.0,
+            "mb" => 1e6,
+            "mib" => 1024.0 * 1024.0,
+            _ => bail!(
+                "invalid size `{}`, expected a unit of B, KB, KiB, MB or MiB",
+                s
+            ),
+        };
+        Ok(ByteSize((number * multiplier).round() as u64))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ByteSize {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bytes(u64),
+            WithUnit(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Bytes(bytes) => Ok(ByteSize(bytes)),
+            Raw::WithUnit(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Render a number of bytes for display.
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// The section and function sizes of the `.wasm` files in an output
+/// directory, saved to compare later builds against.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Every `.wasm` file, sorted by path.
+    pub modules: Vec<ModuleBaseline>,
+}
+
+/// The sizes recorded for one `.wasm` file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleBaseline {
+    /// The path of the file, relative to the output directory.
+    pub path: PathBuf,
+    /// The size of the file.
+    pub size: u64,
+    /// The size of each section, by kind.
+    pub sections: BTreeMap<String, u64>,
+    /// The size of each function body, by name. Empty if the module has no
+    /// `name` section.
+    pub functions: BTreeMap<String, u64>,
+}
+
+impl Baseline {
+    /// Measure the `.wasm` files in `out_dir`.
+    pub fn new(out_dir: &Path) -> Result<Baseline> {
+        let mut modules = Vec::new();
+        let entries = WalkDir::new(out_dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "wasm"));
+        for entry in entries {
+            let wasm = fs::read(entry.path())
+                .with_context(|| format!("failed to read {}", entry.path().display()))?;
+            let context = || format!("failed to parse {}", entry.path().display());
+            let mut sections = BTreeMap::new();
+            for section in self::sections(&wasm).with_context(context)? {
+                *sections.entry(section.name).or_insert(0) += section.size;
+            }
+            modules.push(ModuleBaseline {
+                path: entry.path().strip_prefix(out_dir)?.to_path_buf(),
+                size: wasm.len() as u64,
+                sections,
+                functions: function_sizes(&wasm)
+                    .with_context(context)?
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(Baseline { modules })
+    }
+
+    /// Read a baseline saved with `save`.
+    pub fn load(path: &Path) -> Result<Baseline> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read the baseline at {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse the baseline at {}", path.display()))
+    }
+
+    /// Write this baseline to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write the baseline to {}", path.display()))
+    }
+
+    /// The total size of all of the `.wasm` files.
+    pub fn total(&self) -> u64 {
+        self.modules.iter().map(|module| module.size).sum()
+    }
+
+    /// Compare `current` against this baseline.
+    pub fn compare<'a>(&'a self, current: &'a Baseline) -> Comparison {
+        let mut sections = Vec::new();
+        let mut functions = Vec::new();
+        let paths: BTreeSet<&PathBuf> = self
+            .modules
+            .iter()
+            .chain(&current.modules)
+            .map(|module| &module.path)
+            .collect();
+        for path in paths {
+            let module = |baseline: &'a Baseline| {
+                baseline.modules.iter().find(|module| &module.path == path)
+            };
+            let (before, after) = (module(self), module(current));
+            let empty = BTreeMap::new();
+            sections.extend(changes(
+                path,
+                before.map_or(&empty, |module| &module.sections),
+                after.map_or(&empty, |module| &module.sections),
+            ));
+            functions.extend(changes(
+                path,
+                before.map_or(&empty, |module| &module.functions),
+                after.map_or(&empty, |module| &module.functions),
+            ));
+        }
+        functions.sort_by_key(|change| std::cmp::Reverse(change.delta()));
+        Comparison {
+            before: self.total(),
+            after: current.total(),
+            sections,
+            functions,
+        }
+    }
+}
+
+fn changes(
+    module: &Path,
+    before: &BTreeMap<String, u64>,
+    after: &BTreeMap<String, u64>,
+) -> Vec<Change> {
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    names
+        .into_iter()
+        .map(|name| Change {
+            module: module.to_path_buf(),
+            name: name.clone(),
+            before: before.get(name).copied().unwrap_or(0),
+            after: after.get(name).copied().unwrap_or(0),
+        })
+        .filter(|change| change.before != change.after)
+        .collect()
+}
+
+/// How the size of one section or function changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// The `.wasm` file the section or function is in.
+    pub module: PathBuf,
+    /// The kind of section, or the name of the function.
+    pub name: String,
+    /// The size in the baseline, or 0 if it is new.
+    pub before: u64,
+    /// The current size, or 0 if it was removed.
+    pub after: u64,
+}
+
+impl Change {
+    /// How many bytes it grew by, or shrank by if negative.
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+/// The differences between a baseline and the current build.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    /// The total size of the `.wasm` files in the baseline.
+    pub before: u64,
+    /// The current total size of the `.wasm` files.
+    pub after: u64,
+    /// The sections that changed size.
+    pub sections: Vec<Change>,
+    /// The functions that changed size, largest growth first.
+    pub functions: Vec<Change>,
+}
+
+impl Comparison {
+    /// How many bytes the `.wasm` files grew by, or shrank by if negative.
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+
+    /// The `count` functions that grew the most.
+    pub fn growers(&self, count: usize) -> impl Iterator<Item = &Change> {
+        self.functions
+            .iter()
+            .filter(|change| change.delta() > 0)
+            .take(count)
+    }
+
+    /// The `count` functions that shrank the most.
+    pub fn shrinkers(&self, count: usize) -> impl Iterator<Item = &Change> {
+        self.functions
+            .iter()
+            .rev()
+            .filter(|change| change.delta() < 0)
+            .take(count)
+    }
+
+    /// Render the comparison as a table, listing at most `top` growing and
+    /// shrinking functions.
+    pub fn table(&self, top: usize) -> String {
+        let modules: BTreeSet<&PathBuf> = self
+            .sections
+            .iter()
+            .chain(&self.functions)
+            .map(|change| &change.module)
+            .collect();
+        let multiple_modules = modules.len() > 1;
+        let label = |change: &Change| {
+            if multiple_modules {
+                format!("{}: {}", change.module.display(), change.name)
+            } else {
+                change.name.clone()
+            }
+        };
+        let mut table = String::new();
+        let _ = writeln!(
+            table,
+            "  total: {} -> {} ({})",
+            format_bytes(self.before),
+            format_bytes(self.after),
+            format_delta(self.delta())
+        );
+        let mut list = |title: &str, changes: Vec<&Change>| {
+            if changes.is_empty() {
+                return;
+            }
+            let _ = writeln!(table, "  {}:", title);
+            for change in changes {
+                let _ = writeln!(
+                    table,
+                    "    {:>12}  {}",
+                    format_delta(change.delta()),
+                    label(change)
+                );
+            }
+        };
+        list("sections", self.sections.iter().collect());
+        list("largest growers", self.growers(top).collect());
+        list("largest shrinkers", self.shrinkers(top).collect());
+        table.trim_end().to_string()
+    }
+}
+
+/// How much the `.wasm` files may grow compared to a baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaxGrowth {
+    /// A number of bytes.
+    Bytes(u64),
+    /// A percentage of the baseline's size.
+    Percent(f64),
+}
+
+impl MaxGrowth {
+    /// Whether `comparison` grew by more than allowed.
+    pub fn is_exceeded_by(&self, comparison: &Comparison) -> bool {
+        let delta = comparison.delta();
+        match *self {
+            MaxGrowth::Bytes(bytes) => delta > bytes as i64,
+            MaxGrowth::Percent(percent) => {
+                delta as f64 > comparison.before as f64 * percent / 100.0
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for MaxGrowth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().strip_suffix('%') {
+            Some(percent) => {
+                let percent: f64 = percent
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid percentage `{}`", s))?;
+                Ok(MaxGrowth::Percent(percent))
+            }
+            None => Ok(MaxGrowth::Bytes(s.parse::<ByteSize>()?.0)),
+        }
+    }
+}
+
+impl std::fmt::Display for MaxGrowth {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MaxGrowth::Bytes(bytes) => write!(f, "{}", format_bytes(*bytes)),
+            MaxGrowth::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+/// Render a change in size for display, with its sign.
+pub fn format_delta(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_bytes(delta.unsigned_abs()))
+}
@@ -1,7 +1,27 @@
-//! Key-value store in `*.stamps` file.
+//! Key-value store in the `stamps.json` file.
+//!
+//! The store is kept in `$XDG_STATE_HOME/wasm-pack` if `XDG_STATE_HOME` is
+//! set, and in wasm-pack's cache directory otherwise, rather than next to the
+//! executable, which may not be writable. Stamps that older versions of
+//! wasm-pack kept next to the executable are moved into it the first time it
+//! is read.
 
+use crate::cache;
+use crate::lock::{self, FileLock};
 use anyhow::{anyhow, Context, Result};
-use std::{env, fs, path::PathBuf};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The key of the time that the latest version of wasm-pack was last looked
+/// up.
+pub const WASM_PACK_LAST_CHECKED_STAMP: &str = "wasm_pack_last_checked";
+/// The key of the latest version of wasm-pack, as of the last lookup.
+pub const WASM_PACK_LATEST_VERSION_STAMP: &str = "wasm_pack_latest_version";
+
+/// The name of the file that the store is kept in.
+const STAMPS_FILE: &str = "stamps.json";
 
 /// Get a value corresponding to the key from the JSON value.
 ///
@@ -14,29 +34,48 @@ pub fn get_stamp_value(key: impl AsRef<str>, json: &serde_json::Value) -> Result
 
 /// Save the key-value pair to the store.
 pub fn save_stamp_value(key: impl Into<String>, value: impl AsRef<str>) -> Result<()> {
+    let key = key.into();
+    update_stamps(|stamps| {
+        stamps.insert(key, value.as_ref().into());
+    })
+}
+
+/// Change any number of values in the store at once. The store is locked
+/// while it is updated, so that concurrent processes don't lose each other's
+/// values.
+pub fn update_stamps(
+    update: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>),
+) -> Result<()> {
+    let _lock = FileLock::exclusive(&get_stamps_file_path()?.with_extension("lock"))?;
     let mut json = read_stamps_file_to_json().unwrap_or_else(|_| serde_json::Map::new().into());
 
     {
         let stamps = json
             .as_object_mut()
             .ok_or_else(|| anyhow!("stamps file doesn't contain JSON object"))?;
-        stamps.insert(key.into(), value.as_ref().into());
+        update(stamps);
     }
 
     write_to_stamps_file(json)
 }
 
-/// Get the path of the `*.stamps` file that is used as the store.
+/// Get the path of the `stamps.json` file that is used as the store.
 pub fn get_stamps_file_path() -> Result<PathBuf> {
-    let path = env::current_exe()
-        .map(|path| path.with_extension("stamps"))
-        .context("cannot get stamps file path")?;
-    Ok(path)
+    if let Some(state_home) = env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        return Ok(Path::new(&state_home).join("wasm-pack").join(STAMPS_FILE));
+    }
+    let cache = cache::get_wasm_pack_cache().context("cannot get stamps file path")?;
+    Ok(cache.destination.join(STAMPS_FILE))
 }
 
-/// Read `*.stamps` file and convert its content to the JSON value.
+/// Read `stamps.json` file and convert its content to the JSON value.
 pub fn read_stamps_file_to_json() -> Result<serde_json::Value> {
     let stamps_file_path = get_stamps_file_path()?;
+    if !stamps_file_path.exists() {
+        if let Err(e) = migrate_legacy_stamps(&stamps_file_path) {
+            log::debug!("cannot migrate the stamps next to the executable: {}", e);
+        }
+    }
     let stamps_file_content =
         fs::read_to_string(stamps_file_path).context("cannot find or read stamps file")?;
     let json: serde_json::Value = serde_json::from_str(&stamps_file_content)
@@ -46,7 +85,52 @@ pub fn read_stamps_file_to_json() -> Result<serde_json::Value> {
 
 fn write_to_stamps_file(json: serde_json::Value) -> Result<()> {
     let stamps_file_path = get_stamps_file_path()?;
+    if let Some(dir) = stamps_file_path.parent() {
+        fs::create_dir_all(dir).context("cannot create the stamps file's directory")?;
+    }
     let pretty_json = serde_json::to_string_pretty(&json).context("JSON serialization failed")?;
-    fs::write(stamps_file_path, pretty_json).context("cannot write to stamps file")?;
+    lock::write_atomic(&stamps_file_path, pretty_json).context("cannot write to stamps file")?;
+    Ok(())
+}
+
+/// Move the stamps that older versions of wasm-pack kept next to the
+/// executable into the store at `path`: the JSON `wasm-pack.stamps` file of
+/// the drivers, and the `wasm-pack.stamp` file of the update check, which has
+/// a `created <time>` and a `version <version>` line.
+fn migrate_legacy_stamps(path: &Path) -> Result<()> {
+    let exe = env::current_exe()?;
+    let json_path = exe.with_extension("stamps");
+    let lines_path = exe.with_extension("stamp");
+
+    let mut stamps = serde_json::Map::new();
+    if let Ok(contents) = fs::read_to_string(&json_path) {
+        if let Ok(serde_json::Value::Object(json)) = serde_json::from_str(&contents) {
+            stamps.extend(json);
+        }
+    }
+    if let Ok(contents) = fs::read_to_string(&lines_path) {
+        for line in contents.lines() {
+            let (key, value) = match line.split_once(' ') {
+                Some(("created", value)) => (WASM_PACK_LAST_CHECKED_STAMP, value),
+                Some(("version", value)) => (WASM_PACK_LATEST_VERSION_STAMP, value),
+                _ => continue,
+            };
+            stamps.insert(key.to_string(), value.trim().into());
+        }
+    }
+    if stamps.is_empty() {
+        return Ok(());
+    }
+
+    log::info!(
+        "Moving the stamps next to {} to {}",
+        exe.display(),
+        path.display()
+    );
+    write_to_stamps_file(stamps.into())?;
+    // The executable's directory may be read-only, in which case the old
+    // files stay behind, but they aren't read again.
+    drop(fs::remove_file(json_path));
+    drop(fs::remove_file(lines_path));
     Ok(())
 }
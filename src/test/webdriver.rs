@@ -4,9 +4,11 @@ mod chromedriver;
 mod geckodriver;
 mod safaridriver;
 
+use crate::cache::{self, EntrySource};
+use crate::install;
 use crate::PBAR;
 use anyhow::Result;
-use binary_install::Cache;
+use binary_install::{Cache, Download};
 use std::path::PathBuf;
 
 pub use self::{
@@ -21,16 +23,34 @@ fn get_and_notify(
     cache: &Cache,
     installation_allowed: bool,
     name: &str,
+    version: &str,
     url: &str,
 ) -> Result<Option<PathBuf>> {
-    if let Some(dl) = cache.download(false, name, &[name], url)? {
-        return Ok(Some(dl.binary(name)?));
-    }
-    if installation_allowed {
-        PBAR.info(&format!("Getting {}...", name));
-    }
-    match cache.download(installation_allowed, name, &[name], url)? {
-        Some(dl) => Ok(Some(dl.binary(name)?)),
-        None => Ok(None),
-    }
+    let (dl, version) = match install::download::download(cache, false, name, &[name], url)? {
+        Some(dl) => (dl, version.to_string()),
+        None => {
+            if installation_allowed {
+                PBAR.info(&format!("Getting {}...", name));
+            }
+            match install::download::download(cache, installation_allowed, name, &[name], url)? {
+                Some(dl) => (dl, version.to_string()),
+                // Without installing, a version that was fetched before, for
+                // example by `wasm-pack fetch`, will do if it has the same
+                // major version, which is the browser version that
+                // chromedriver supports.
+                None => match cache::latest_entry(cache, name, |cached| {
+                    major_version(cached) == major_version(version)
+                }) {
+                    Some(entry) => (Download::at(&entry.path), entry.version.unwrap_or_default()),
+                    None => return Ok(None),
+                },
+            }
+        }
+    };
+    cache::record_use(&dl.path(), name, Some(&version), EntrySource::Download);
+    Ok(Some(dl.binary(name)?))
+}
+
+fn major_version(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
 }
@@ -1,4 +1,5 @@
 use super::get_and_notify;
+use crate::config;
 use crate::install::InstallMode;
 use crate::stamps;
 use crate::target;
@@ -38,9 +39,9 @@ pub fn install_chromedriver(cache: &Cache, installation_allowed: bool) -> Result
         bail!("chromedriver binaries are unavailable for this target")
     };
 
-    let url = get_chromedriver_url(target);
+    let (version, url) = get_chromedriver_url(target);
 
-    match get_and_notify(cache, installation_allowed, "chromedriver", &url)? {
+    match get_and_notify(cache, installation_allowed, "chromedriver", &version, &url)? {
         Some(path) => Ok(path),
         None => bail!(
             "No cached `chromedriver` binary found, and could not find a global \
@@ -50,7 +51,7 @@ pub fn install_chromedriver(cache: &Cache, installation_allowed: bool) -> Result
     }
 }
 
-/// Get `chromedriver` download URL.
+/// Get `chromedriver` version and download URL.
 ///
 /// _Algorithm_:
 /// 1. Try to open `*.stamps` file and deserialize its content to JSON object.
@@ -58,7 +59,7 @@ pub fn install_chromedriver(cache: &Cache, installation_allowed: bool) -> Result
 /// 3. If the saved time is older than 1 day or something failed
 ///    => fetch a new version and save version & time.
 /// 4. If everything failed, use the default version.
-/// 5. Return URL.
+/// 5. Return the version and URL.
 ///
 /// _Notes:_
 ///
@@ -68,7 +69,7 @@ pub fn install_chromedriver(cache: &Cache, installation_allowed: bool) -> Result
 ///
 /// The official algorithm for `chromedriver` version selection:
 /// https://chromedriver.chromium.org/downloads/version-selection
-fn get_chromedriver_url(target: &str) -> String {
+fn get_chromedriver_url(target: &str) -> (String, String) {
     let fetch_and_save_version =
         || fetch_chromedriver_version().and_then(save_chromedriver_version);
 
@@ -91,7 +92,8 @@ fn get_chromedriver_url(target: &str) -> String {
         );
         DEFAULT_CHROMEDRIVER_VERSION.to_owned()
     });
-    assemble_chromedriver_url(&chromedriver_version, target)
+    let url = assemble_chromedriver_url(&chromedriver_version, target);
+    (chromedriver_version, url)
 }
 
 // ------ `get_chromedriver_url` helpers ------
@@ -156,6 +158,9 @@ fn fetch_chromedriver_version() -> Result<String> {
 }
 
 fn assemble_chromedriver_url(chromedriver_version: &str, target: &str) -> String {
+    if let Some(url) = config::get().mirror_url("chromedriver", chromedriver_version, target) {
+        return url;
+    }
     format!(
         "https://storage.googleapis.com/chrome-for-testing-public/{version}/{target}/chromedriver-{target}.zip",
         version = chromedriver_version,
@@ -1,4 +1,5 @@
 use super::get_and_notify;
+use crate::config;
 use crate::install::InstallMode;
 use crate::stamps;
 use crate::target;
@@ -52,9 +53,9 @@ pub fn install_geckodriver(cache: &Cache, installation_allowed: bool) -> Result<
         bail!("geckodriver binaries are unavailable for this target")
     };
 
-    let url = get_geckodriver_url(target, ext);
+    let (version, url) = get_geckodriver_url(target, ext);
 
-    match get_and_notify(cache, installation_allowed, "geckodriver", &url)? {
+    match get_and_notify(cache, installation_allowed, "geckodriver", &version, &url)? {
         Some(path) => Ok(path),
         None => bail!(
             "No cached `geckodriver` binary found, and could not find a global `geckodriver` \
@@ -63,7 +64,7 @@ pub fn install_geckodriver(cache: &Cache, installation_allowed: bool) -> Result<
     }
 }
 
-/// Get `geckodriver` download URL.
+/// Get `geckodriver` version and download URL.
 ///
 /// _Algorithm_:
 /// 1. Try to open `*.stamps` file and deserialize its content to JSON object.
@@ -71,14 +72,14 @@ pub fn install_geckodriver(cache: &Cache, installation_allowed: bool) -> Result<
 /// 3. If the saved time is older than 1 day or something failed
 ///    => fetch a new version and save version & time.
 /// 4. If everything failed, use the default version.
-/// 5. Return URL.
+/// 5. Return the version and URL.
 ///
 /// _Notes:_
 ///
 /// It returns the latest one without checking the installed `Firefox` version
 /// - it should be relatively safe because each `geckodriver` supports many `Firefox` versions:
 /// https://firefox-source-docs.mozilla.org/testing/geckodriver/Support.html#supported-platforms
-fn get_geckodriver_url(target: &str, ext: &str) -> String {
+fn get_geckodriver_url(target: &str, ext: &str) -> (String, String) {
     let fetch_and_save_version =
         || fetch_latest_geckodriver_tag_json().and_then(save_geckodriver_version);
 
@@ -112,7 +113,7 @@ fn get_geckodriver_url(target: &str, ext: &str) -> String {
     };
     let url = assemble_geckodriver_url(&geckodriver_version, target, ext);
     log::info!("[geckodriver] Fetching geckodriver at {}", url);
-    url
+    (geckodriver_version, url)
 }
 
 // ------ `get_geckodriver_url` helpers  ------
@@ -161,6 +162,9 @@ fn get_version_from_json(json: serde_json::Value) -> Result<String> {
 }
 
 fn assemble_geckodriver_url(tag: &str, target: &str, ext: &str) -> String {
+    if let Some(url) = config::get().mirror_url("geckodriver", tag, target) {
+        return url;
+    }
     format!(
         "https://github.com/mozilla/geckodriver/releases/download/{tag}/geckodriver-{tag}-{target}.{ext}",
         tag=tag,
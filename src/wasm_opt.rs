@@ -1,23 +1,18 @@
 //! Support for downloading and executing `wasm-opt`
 
 use crate::child;
-use crate::install;
+use crate::emoji;
+use crate::install::{self, Tool};
 use crate::PBAR;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use binary_install::Cache;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
 
-/// Execute `wasm-opt` over wasm binaries found in `out_dir`, downloading if
-/// necessary into `cache`. Passes `args` to each invocation of `wasm-opt`.
-pub fn run(cache: &Cache, out_dir: &Path, args: &[String], install_permitted: bool) -> Result<()> {
-    let wasm_opt_path = match find_wasm_opt(cache, install_permitted)? {
-        Some(path) => path,
-        // `find_wasm_opt` will have already logged a message about this, so we don't need to here.
-        None => return Ok(()),
-    };
-
+/// Execute the `wasm-opt` at `wasm_opt_path` over wasm binaries found in
+/// `out_dir`. Passes `args` to each invocation of `wasm-opt`.
+pub fn run(wasm_opt_path: &Path, out_dir: &Path, args: &[String]) -> Result<()> {
     PBAR.info("Optimizing wasm binaries with `wasm-opt`...");
 
     for file in out_dir.read_dir()? {
@@ -28,7 +23,7 @@ pub fn run(cache: &Cache, out_dir: &Path, args: &[String], install_permitted: bo
         }
 
         let tmp = path.with_extension("wasm-opt.wasm");
-        let mut cmd = Command::new(&wasm_opt_path);
+        let mut cmd = Command::new(wasm_opt_path);
         cmd.arg(&path).arg("-o").arg(&tmp).args(args);
         child::run(cmd, "wasm-opt")?;
         std::fs::rename(&tmp, &path)?;
@@ -37,22 +32,52 @@ pub fn run(cache: &Cache, out_dir: &Path, args: &[String], install_permitted: bo
     Ok(())
 }
 
-/// Attempts to find `wasm-opt` in `PATH` locally, or failing that downloads a
-/// precompiled binary.
+/// Uses the `wasm-opt` set in the config or by `WASM_PACK_WASM_OPT`, or else
+/// attempts to find `wasm-opt` in `PATH` locally, or failing that downloads a
+/// precompiled binary. If `version` is pinned, a `wasm-opt` in `PATH` is only
+/// used if it is that version, and otherwise that version is downloaded.
 ///
 /// Returns `Some` if a binary was found or it was successfully downloaded.
 /// Returns `None` if a binary wasn't found in `PATH` and this platform doesn't
 /// have precompiled binaries. Returns an error if we failed to download the
 /// binary.
-pub fn find_wasm_opt(cache: &Cache, install_permitted: bool) -> Result<Option<PathBuf>> {
-    // First attempt to look up in PATH. If found assume it works.
-    if let Ok(path) = which::which("wasm-opt") {
-        PBAR.info(&format!("found wasm-opt at {:?}", path));
+pub fn find_wasm_opt(
+    cache: &Cache,
+    version: Option<&str>,
+    install_permitted: bool,
+) -> Result<Option<PathBuf>> {
+    if let Some(path) =
+        install::configured_tool(&Tool::WasmOpt, "wasm-opt", version.unwrap_or("latest"))?
+    {
+        PBAR.info(&format!("using wasm-opt at {:?}", path));
         return Ok(Some(path));
     }
 
-    match install::download_prebuilt(&install::Tool::WasmOpt, cache, "latest", install_permitted)? {
+    // Then attempt to look up in PATH. If found and nothing is pinned, assume
+    // it works.
+    if let Ok(path) = which::which("wasm-opt") {
+        match version {
+            None => {
+                PBAR.info(&format!("found wasm-opt at {:?}", path));
+                return Ok(Some(path));
+            }
+            Some(version) => {
+                if install::check_version(&Tool::WasmOpt, &path, version)? {
+                    PBAR.info(&format!("found wasm-opt {} at {:?}", version, path));
+                    return Ok(Some(path));
+                }
+                PBAR.info(&format!(
+                    "Not using the wasm-opt at {:?}, because the crate pins {}",
+                    path, version
+                ));
+            }
+        }
+    }
+
+    let version = version.unwrap_or("latest");
+    match install(cache, version, install_permitted)? {
         install::Status::Found(download) => Ok(Some(download.binary("bin/wasm-opt")?)),
+        install::Status::Configured(path) => Ok(Some(path)),
         install::Status::CannotInstall => {
             PBAR.info("Skipping wasm-opt as no downloading was requested");
             Ok(None)
@@ -63,3 +88,26 @@ pub fn find_wasm_opt(cache: &Cache, install_permitted: bool) -> Result<Option<Pa
         }
     }
 }
+
+/// Install `version` of `wasm-opt` into `cache`: download binaryen's release
+/// if there is one for this platform, and otherwise build the `wasm-opt`
+/// crate's binary with `cargo install`.
+pub fn install(cache: &Cache, version: &str, install_permitted: bool) -> Result<install::Status> {
+    if install::has_prebuilt(&Tool::WasmOpt) {
+        return install::download_prebuilt(&Tool::WasmOpt, cache, version, install_permitted);
+    }
+
+    let status = install::cargo_install(Tool::WasmOpt, cache, version, false)?;
+    if !install_permitted || !matches!(status, install::Status::CannotInstall) {
+        return Ok(status);
+    }
+    PBAR.info(&format!(
+        "{}binaryen has no release for this platform, so wasm-opt is being built with \
+         `cargo install wasm-opt`. This needs a C++ compiler and takes a few minutes.",
+        emoji::DOWN_ARROW
+    ));
+    install::cargo_install(Tool::WasmOpt, cache, version, true).context(
+        "Install binaryen's wasm-opt yourself, or skip it with `wasm-opt = false` in \
+         [package.metadata.wasm-pack.profile.release]",
+    )
+}
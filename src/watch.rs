@@ -0,0 +1,172 @@
+//! Watching a crate's sources for changes, and syncing rebuilt output.
+
+use crate::fingerprint::{Fingerprints, FINGERPRINT_FILE};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// How often the watched directories are scanned for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The modification time and length of every file under a set of watched
+/// directories, used to detect changes between two points in time.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+/// A set of directories to watch, along with directories inside them whose
+/// contents should be ignored, such as the target and output directories.
+pub struct Watcher {
+    roots: Vec<PathBuf>,
+    ignored: Vec<PathBuf>,
+}
+
+impl Watcher {
+    /// Watch `roots`, except for anything inside `ignored`.
+    pub fn new(roots: Vec<PathBuf>, ignored: Vec<PathBuf>) -> Watcher {
+        let canonical = |paths: Vec<PathBuf>| -> Vec<PathBuf> {
+            paths
+                .into_iter()
+                .map(|path| fs::canonicalize(&path).unwrap_or(path))
+                .collect()
+        };
+        Watcher {
+            roots: canonical(roots),
+            ignored: canonical(ignored),
+        }
+    }
+
+    /// The directories being watched.
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Take a snapshot of the files currently in the watched directories.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut files = BTreeMap::new();
+        for root in &self.roots {
+            let entries = WalkDir::new(root)
+                .into_iter()
+                .filter_entry(|entry| !self.is_ignored(entry.path()))
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file());
+            for entry in entries {
+                if let Ok(metadata) = entry.metadata() {
+                    files.insert(
+                        entry.into_path(),
+                        (metadata.modified().ok(), metadata.len()),
+                    );
+                }
+            }
+        }
+        Snapshot { files }
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.') || name == "node_modules");
+        hidden || self.ignored.iter().any(|ignored| path.starts_with(ignored))
+    }
+
+    /// Block until the watched files differ from `previous`, and then until
+    /// they have stopped changing for `debounce`. Returns the new snapshot.
+    pub fn wait_for_change(&self, previous: &Snapshot, debounce: Duration) -> Snapshot {
+        let mut current = loop {
+            thread::sleep(POLL_INTERVAL);
+            let current = self.snapshot();
+            if current != *previous {
+                break current;
+            }
+        };
+        loop {
+            thread::sleep(debounce);
+            let next = self.snapshot();
+            if next == current {
+                return current;
+            }
+            current = next;
+        }
+    }
+}
+
+/// Remove everything from the staging directory `dir` except the fingerprints
+/// of the last build and the bindings they record, so that outputs which are
+/// no longer generated disappear while unchanged wasm isn't processed again.
+pub fn clean_staging_dir(dir: &Path) -> Result<()> {
+    let fingerprints = Fingerprints::load(dir);
+    let kept: Vec<&Path> = fingerprints.outputs().collect();
+    for entry in WalkDir::new(dir).min_depth(1).contents_first(true) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let relative = entry.path().strip_prefix(dir)?;
+        if relative == Path::new(FINGERPRINT_FILE)
+            || kept.iter().any(|output| relative.starts_with(output))
+        {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            // Directories that still hold kept outputs can't be removed.
+            let _ = fs::remove_dir(entry.path());
+        } else {
+            fs::remove_file(entry.path())
+                .with_context(|| format!("failed to remove {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Make `to` match `from`, only writing the files whose contents differ.
+///
+/// Files in `to` that are listed in `previously_synced` but no longer exist
+/// in `from` are removed; anything else already in `to` is left alone. Returns
+/// the set of files now synced, relative to `to`, and the files that were
+/// written or removed.
+pub fn sync_dir(
+    from: &Path,
+    to: &Path,
+    previously_synced: &BTreeSet<PathBuf>,
+) -> Result<(BTreeSet<PathBuf>, Vec<PathBuf>)> {
+    let mut synced = BTreeSet::new();
+    let mut changed = Vec::new();
+
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(from)?.to_path_buf();
+        let destination = to.join(&relative);
+        let contents = fs::read(entry.path())
+            .with_context(|| format!("failed to read {}", entry.path().display()))?;
+        let unchanged = fs::read(&destination).is_ok_and(|existing| existing == contents);
+        if !unchanged {
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&destination, &contents)
+                .with_context(|| format!("failed to write {}", destination.display()))?;
+            changed.push(relative.clone());
+        }
+        synced.insert(relative);
+    }
+
+    for stale in previously_synced.difference(&synced) {
+        let path = to.join(stale);
+        if path.is_file() {
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove {}", path.display()))?;
+            changed.push(stale.clone());
+        }
+    }
+
+    Ok((synced, changed))
+}
@@ -1,5 +1,6 @@
 use crate::utils;
 use assert_cmd::prelude::*;
+use predicates::prelude::*;
 use std::fs;
 use std::path::Path;
 
@@ -99,6 +100,112 @@ fn it_should_build_crates_in_a_workspace() {
         .success();
 }
 
+#[test]
+fn it_should_build_all_cdylib_workspace_members() {
+    let fixture = utils::fixture::workspace_with_cdylib_members();
+    fixture.install_local_wasm_bindgen();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--workspace")
+        .assert()
+        .success();
+
+    for name in &["first", "second"] {
+        let pkg_path = fixture.path.join(name).join("pkg");
+        assert!(pkg_path.join("package.json").exists());
+        assert!(pkg_path.join(format!("{}_bg.wasm", name)).exists());
+    }
+    assert!(!fixture.path.join("helper").join("pkg").exists());
+}
+
+#[test]
+fn workspace_members_are_compiled_with_their_own_cargo_args() {
+    let fixture = utils::fixture::workspace_with_cdylib_members();
+    // Neither member needs wasm-bindgen to compile.
+    fixture
+        .file(
+            "first/Cargo.toml",
+            r#"
+                [package]
+                authors = ["The wasm-pack developers"]
+                name = "first"
+                version = "0.1.0"
+
+                [lib]
+                crate-type = ["cdylib"]
+            "#,
+        )
+        .file("first/src/lib.rs", "pub fn hello() -> u32 { 42 }")
+        .file(
+            "second/Cargo.toml",
+            r#"
+                [package]
+                authors = ["The wasm-pack developers"]
+                description = "so awesome rust+wasm package"
+                license = "WTFPL"
+                name = "second"
+                repository = "https://github.com/rustwasm/wasm-pack.git"
+                version = "0.1.0"
+
+                [lib]
+                crate-type = ["cdylib"]
+
+                [features]
+                fast = []
+
+                [package.metadata.wasm-pack]
+                cargo-args = ["--features", "fast"]
+            "#,
+        )
+        .file(
+            "second/src/lib.rs",
+            r#"
+                #[cfg(not(feature = "fast"))]
+                compile_error!("built without the fast feature");
+            "#,
+        );
+    let output = fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--workspace")
+        .arg("--mode")
+        .arg("no-install")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !stderr.contains("built without the fast feature"),
+        "{}",
+        stderr
+    );
+    // The members differ in their cargo arguments, so each is compiled by
+    // its own cargo invocation, and both compile.
+    assert_eq!(
+        stderr.matches("Compiling to Wasm...").count(),
+        2,
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Finished `release` profile"), "{}", stderr);
+    assert!(!stderr.contains("Compiling your crate to WebAssembly failed"));
+}
+
+#[test]
+fn build_unknown_workspace_package_errors() {
+    let fixture = utils::fixture::workspace_with_cdylib_members();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("-p")
+        .arg("missing")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "`missing` is not a member of the workspace",
+        ));
+}
+
 #[test]
 fn renamed_crate_name_works() {
     let fixture = utils::fixture::Fixture::new();
@@ -420,3 +527,161 @@ fn build_crates_with_same_names() {
         .assert()
         .success();
 }
+
+#[test]
+fn build_skips_bindgen_when_wasm_is_unchanged() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture.install_local_wasm_bindgen();
+    fixture.wasm_pack().arg("build").assert().success();
+    assert!(fixture
+        .path
+        .join("pkg")
+        .join(".wasm-pack-fingerprint.json")
+        .exists());
+
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "skipping wasm-bindgen and wasm-opt",
+        ));
+
+    // Different flags for wasm-bindgen mean the bindings are generated again.
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--no-typescript")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("skipping wasm-bindgen").not());
+}
+
+#[test]
+fn build_passes_custom_rust_target_to_cargo() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture.file("my-target.json", "{}");
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--rust-target")
+        .arg("my-target.json")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("my-target"))
+        .stderr(predicates::str::contains(
+            "Compiling your crate to WebAssembly failed",
+        ));
+}
+
+#[test]
+fn build_errors_are_reported_before_download_errors() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture.file("src/lib.rs", "this is not rust");
+    fixture
+        .wasm_pack()
+        .env(
+            "WASM_PACK_WASM_BINDGEN",
+            fixture.path.join("missing/wasm-bindgen"),
+        )
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Compiling your crate to WebAssembly failed",
+        ))
+        .stderr(predicates::str::contains("WASM_PACK_WASM_BINDGEN").not());
+}
+
+#[test]
+fn rust_target_names() {
+    use wasm_pack::build::wasm_target::{runner_env_var, rust_target_name};
+
+    assert_eq!(rust_target_name("wasm32v1-none"), "wasm32v1-none");
+    assert_eq!(rust_target_name("/targets/my-target.json"), "my-target");
+    assert_eq!(
+        runner_env_var("wasm32-unknown-unknown"),
+        "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER"
+    );
+    assert_eq!(
+        runner_env_var("wasm32-wasip1"),
+        "CARGO_TARGET_WASM32_WASIP1_RUNNER"
+    );
+}
+
+#[test]
+fn cargo_build_wasm_reports_the_artifact_in_a_custom_target_dir() {
+    use wasm_pack::build::cargo_build_wasm;
+    use wasm_pack::command::build::BuildProfile;
+
+    let fixture = utils::fixture::Fixture::new();
+    fixture
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "no-deps"
+                version = "0.1.0"
+                edition = "2021"
+
+                [lib]
+                crate-type = ["cdylib", "rlib"]
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            "#[no_mangle] pub extern \"C\" fn answer() -> u32 { 42 }",
+        );
+    let target_dir = fixture.path.join("elsewhere");
+
+    let artifacts = cargo_build_wasm(
+        &fixture.path,
+        BuildProfile::Dev,
+        "wasm32-unknown-unknown",
+        false,
+        &[format!("--target-dir={}", target_dir.display())],
+    )
+    .unwrap();
+
+    assert_eq!(artifacts.len(), 1);
+    assert_eq!(
+        artifacts[0].path,
+        target_dir.join("wasm32-unknown-unknown/debug/no_deps.wasm")
+    );
+    assert!(artifacts[0].path.is_file());
+}
+
+#[test]
+fn threaded_builds_add_to_the_configured_rustflags() {
+    // Flags set in the environment replace the configured ones, and are
+    // extended instead.
+    if std::env::var_os("RUSTFLAGS").is_some()
+        || std::env::var_os("CARGO_ENCODED_RUSTFLAGS").is_some()
+    {
+        return;
+    }
+    let fixture = utils::fixture::Fixture::new();
+    fixture.file("Cargo.toml", "");
+    let mut cmd = std::process::Command::new("cargo");
+    wasm_pack::build::threads::configure_cargo_build(
+        &mut cmd,
+        &fixture.path,
+        "wasm32-unknown-unknown",
+        &[],
+    );
+
+    let args: Vec<String> = cmd
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let config = args
+        .iter()
+        .skip_while(|arg| *arg != "--config")
+        .nth(1)
+        .unwrap();
+    assert!(config.starts_with("target.\"wasm32-unknown-unknown\".rustflags=[\"-C\", "));
+    assert!(config.contains("\"target-feature=+atomics,+bulk-memory,+mutable-globals\""));
+    assert!(cmd.get_envs().all(|(key, _)| key != "RUSTFLAGS"));
+    assert_eq!(args[args.len() - 2..], ["-Z", "build-std=panic_abort,std"]);
+}
@@ -0,0 +1,277 @@
+use crate::utils::fixture;
+use assert_cmd::prelude::*;
+use binary_install::Cache;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use wasm_pack::cache::{self, Age, EntrySource};
+use wasm_pack::lock::FileLock;
+
+/// A cache in the fixture with a downloaded and a `cargo install`ed
+/// wasm-bindgen, a downloaded wasm-opt, and an interrupted `cargo install`.
+fn cache_with_entries(fixture: &fixture::Fixture) -> Cache {
+    let dir = fixture.path.join("cache");
+    for (name, file) in [
+        ("wasm-bindgen-0123456789abcdef", "wasm-bindgen"),
+        ("wasm-bindgen-cargo-install-0.2.80", "wasm-bindgen"),
+        ("wasm-opt-fedcba9876543210", "bin/wasm-opt"),
+        (".wasm-bindgen-cargo-install-0.2.81", "bin/wasm-bindgen"),
+    ] {
+        let path = dir.join(name).join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "binary").unwrap();
+    }
+    cache::record_use(
+        &dir.join("wasm-bindgen-0123456789abcdef"),
+        "wasm-bindgen",
+        Some("0.2.87"),
+        EntrySource::Download,
+    );
+    Cache::at(&dir)
+}
+
+fn names(entries: &[cache::Entry]) -> Vec<&str> {
+    entries
+        .iter()
+        .map(|entry| entry.path.file_name().unwrap().to_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn it_lists_cache_entries() {
+    let fixture = fixture::Fixture::new();
+    let cache = cache_with_entries(&fixture);
+
+    let mut entries: Vec<(String, Option<String>, EntrySource, u64)> = cache::entries(&cache)
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.tool, entry.version, entry.source, entry.size))
+        .collect();
+    entries.sort_by(|a, b| a.1.cmp(&b.1));
+    assert_eq!(
+        entries,
+        vec![
+            ("wasm-opt".to_string(), None, EntrySource::Download, 6),
+            (
+                "wasm-bindgen".to_string(),
+                Some("0.2.80".to_string()),
+                EntrySource::CargoInstall,
+                6
+            ),
+            (
+                "wasm-bindgen".to_string(),
+                Some("0.2.81".to_string()),
+                EntrySource::Partial,
+                6
+            ),
+            (
+                "wasm-bindgen".to_string(),
+                Some("0.2.87".to_string()),
+                EntrySource::Download,
+                6
+            ),
+        ]
+    );
+}
+
+#[test]
+fn prune_skips_partial_entries_that_are_being_created() {
+    let fixture = fixture::Fixture::new();
+    let cache = cache_with_entries(&fixture);
+    let lock_path = cache.join(Path::new(".wasm-bindgen-cargo-install-0.2.81.lock"));
+
+    let lock = FileLock::exclusive(&lock_path).unwrap();
+    assert!(FileLock::try_exclusive(&lock_path).unwrap().is_none());
+    assert!(cache::prune(&cache, None, None).unwrap().is_empty());
+
+    drop(lock);
+    let removed = cache::prune(&cache, None, None).unwrap();
+    assert_eq!(names(&removed), vec![".wasm-bindgen-cargo-install-0.2.81"]);
+    // The lock file is kept for processes that may be waiting on it.
+    assert!(lock_path.exists());
+}
+
+#[test]
+fn prune_skips_complete_entries_that_are_locked() {
+    let fixture = fixture::Fixture::new();
+    let cache = cache_with_entries(&fixture);
+    let lock_path = cache.join(Path::new(".wasm-bindgen-cargo-install-0.2.80.lock"));
+
+    let lock = FileLock::exclusive(&lock_path).unwrap();
+    let removed = cache::prune(&cache, None, Some(0)).unwrap();
+    assert_eq!(removed.len(), 3);
+    assert!(cache
+        .join(Path::new("wasm-bindgen-cargo-install-0.2.80"))
+        .is_dir());
+    drop(lock);
+}
+
+#[test]
+fn prune_removes_partial_entries_and_keeps_the_latest() {
+    let fixture = fixture::Fixture::new();
+    let cache = cache_with_entries(&fixture);
+
+    let removed = cache::prune(&cache, None, None).unwrap();
+    assert_eq!(names(&removed), vec![".wasm-bindgen-cargo-install-0.2.81"]);
+
+    // Nothing is older than a day.
+    let removed = cache::prune(&cache, Some("1d".parse().unwrap()), None).unwrap();
+    assert!(removed.is_empty());
+
+    let removed = cache::prune(&cache, None, Some(1)).unwrap();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].tool, "wasm-bindgen");
+    let remaining = cache::entries(&cache).unwrap();
+    assert_eq!(remaining.len(), 2);
+    assert!(!removed[0].path.exists());
+    let name = removed[0].path.file_name().unwrap().to_str().unwrap();
+    assert!(!cache.join(Path::new(&format!(".{}.json", name))).exists());
+}
+
+#[test]
+fn only_cached_tools_are_entries_of_a_shared_directory() {
+    let fixture = fixture::Fixture::new();
+    let cache = cache_with_entries(&fixture);
+    for dir in [".git/objects", "node_modules/left-pad", "my-project"] {
+        fs::create_dir_all(cache.join(Path::new(dir))).unwrap();
+    }
+    let lock_path = cache.join(Path::new(".wasm-bindgen-cargo-install-0.2.80.lock"));
+
+    assert_eq!(cache::entries(&cache).unwrap().len(), 4);
+
+    let lock = FileLock::exclusive(&lock_path).unwrap();
+    let removed = cache::clean(&cache).unwrap();
+    assert_eq!(removed.len(), 3);
+    assert_eq!(
+        names(&cache::entries(&cache).unwrap()),
+        vec!["wasm-bindgen-cargo-install-0.2.80"]
+    );
+    drop(lock);
+
+    for dir in [".git/objects", "node_modules/left-pad", "my-project"] {
+        assert!(cache.join(Path::new(dir)).is_dir());
+    }
+}
+
+#[test]
+fn latest_entry_only_matches_recorded_versions() {
+    let fixture = fixture::Fixture::new();
+    let dir = fixture.path.join("cache");
+    for (name, version) in [
+        ("chromedriver-0123456789abcdef", Some("125.0.6422.141")),
+        ("chromedriver-fedcba9876543210", None),
+    ] {
+        fs::create_dir_all(dir.join(name)).unwrap();
+        if let Some(version) = version {
+            cache::record_use(
+                &dir.join(name),
+                "chromedriver",
+                Some(version),
+                EntrySource::Download,
+            );
+        }
+    }
+    let cache = Cache::at(&dir);
+    let major = |wanted: &'static str| {
+        move |version: &str| version.split('.').next() == wanted.split('.').next()
+    };
+
+    let entry = cache::latest_entry(&cache, "chromedriver", major("125.0.6422.60")).unwrap();
+    assert_eq!(entry.version.as_deref(), Some("125.0.6422.141"));
+    // Entries without a recorded version, and other major versions, are never
+    // used in place of the wanted version.
+    assert!(cache::latest_entry(&cache, "chromedriver", major("126.0.6478.126")).is_none());
+}
+
+#[test]
+fn it_parses_ages() {
+    assert_eq!("30d".parse::<Age>().unwrap().0.as_secs(), 30 * 24 * 60 * 60);
+    assert_eq!("12h".parse::<Age>().unwrap().0.as_secs(), 12 * 60 * 60);
+    assert_eq!("2w".parse::<Age>().unwrap().0.as_secs(), 14 * 24 * 60 * 60);
+    assert!("30".parse::<Age>().is_err());
+    assert!("d".parse::<Age>().is_err());
+}
+
+#[test]
+fn cache_commands_list_and_clean_the_cache() {
+    let fixture = fixture::Fixture::new();
+    let cache = cache_with_entries(&fixture);
+
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", &cache.destination)
+        .arg("cache")
+        .arg("path")
+        .assert()
+        .success()
+        .stdout(format!("{}\n", cache.destination.display()));
+
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", &cache.destination)
+        .arg("cache")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "TOOL          VERSION  SIZE  SOURCE",
+        ))
+        .stdout(predicate::str::contains(
+            "wasm-bindgen  0.2.80   6 B   cargo install",
+        ))
+        .stdout(predicate::str::contains(
+            "wasm-bindgen  0.2.81   6 B   partial",
+        ));
+
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", &cache.destination)
+        .arg("cache")
+        .arg("clean")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Removed 4 cache entries, freeing 24 B",
+        ));
+    assert!(cache::entries(&cache).unwrap().is_empty());
+}
+
+#[test]
+fn exported_entries_can_be_imported_into_another_cache() {
+    let fixture = fixture::Fixture::new();
+    let cache = cache_with_entries(&fixture);
+    let tarball = fixture.path.join("tools.tar.gz");
+
+    let exported = cache::export(&cache, &tarball).unwrap();
+    assert_eq!(exported.len(), 3);
+
+    let other = fixture.path.join("other-cache");
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", &other)
+        .arg("fetch")
+        .arg("--import")
+        .arg(&tarball)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "Imported 3 cache entries into {}",
+            other.display()
+        )));
+
+    let imported = cache::entries(&Cache::at(&other)).unwrap();
+    assert_eq!(imported.len(), 3);
+    let bindgen = imported
+        .iter()
+        .find(|entry| entry.version.as_deref() == Some("0.2.87"))
+        .unwrap();
+    assert_eq!(bindgen.source, EntrySource::Download);
+    assert_eq!(
+        fs::read_to_string(bindgen.path.join("wasm-bindgen")).unwrap(),
+        "binary"
+    );
+
+    // Importing again keeps the entries that are already there.
+    let imported = cache::import(&Cache::at(&other), &tarball).unwrap();
+    assert!(imported.is_empty());
+}
@@ -0,0 +1,296 @@
+use crate::utils::fixture;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use wasm_pack::config::{Config, Source};
+use wasm_pack::progressbar::LogLevel;
+
+#[test]
+fn project_config_takes_precedence_over_user_config() {
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file(
+            "wasm-pack.toml",
+            r#"
+                npm-client = "pnpm"
+                cache-dir = "cache"
+            "#,
+        )
+        .file(
+            "user/config.toml",
+            r#"
+                npm-client = "yarn"
+                log-level = "warn"
+            "#,
+        )
+        .file("crate/src/lib.rs", "");
+
+    let user_config = fixture.path.join("user/config.toml");
+    let config =
+        Config::load_from(&fixture.path.join("crate/src"), Some(user_config.clone())).unwrap();
+
+    let npm_client = config.npm_client();
+    assert_eq!(npm_client.value, "pnpm");
+    assert_eq!(
+        npm_client.source,
+        Source::File(fixture.path.join("wasm-pack.toml"))
+    );
+    let log_level = config.log_level();
+    assert!(matches!(log_level.value, LogLevel::Warn));
+    assert_eq!(log_level.source, Source::File(user_config));
+    assert!(config.update_check().value);
+    assert_eq!(config.update_check().source, Source::Default);
+}
+
+#[test]
+fn relative_paths_are_relative_to_the_config_file() {
+    let fixture = fixture::Fixture::new();
+    fixture.file(
+        "wasm-pack.toml",
+        r#"
+            chromedriver = "bin/chromedriver"
+            wasm-bindgen = "bin/wasm-bindgen"
+
+            [mirrors]
+            wasm-opt = "https://example.com/{tool}/{version}/{target}.tar.gz"
+        "#,
+    );
+
+    let config = Config::load_from(&fixture.path, None).unwrap();
+    assert_eq!(
+        config.chromedriver().unwrap().value,
+        fixture.path.join("bin/chromedriver")
+    );
+    assert_eq!(
+        config.mirror("wasm-opt").unwrap().value,
+        "https://example.com/{tool}/{version}/{target}.tar.gz"
+    );
+    assert!(config.mirror("wasm-bindgen").is_none());
+    assert_eq!(
+        config.tool("wasm-bindgen").unwrap().value,
+        fixture.path.join("bin/wasm-bindgen")
+    );
+    assert!(config.tool("test-runner").is_none());
+}
+
+#[test]
+fn it_rejects_an_invalid_log_level() {
+    let fixture = fixture::Fixture::new();
+    fixture.file("wasm-pack.toml", "log-level = \"loud\"\n");
+
+    let err = Config::load_from(&fixture.path, None).unwrap_err();
+    assert!(err.to_string().contains("Unknown log-level: loud"));
+}
+
+#[test]
+fn config_list_shows_where_settings_come_from() {
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file("wasm-pack.toml", "npm-client = \"pnpm\"\nbogus = 1\n")
+        .file("xdg/wasm-pack/config.toml", "update-check = false\n");
+    fixture
+        .wasm_pack()
+        .env("XDG_CONFIG_HOME", fixture.path.join("xdg"))
+        .arg("config")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "npm-client = \"pnpm\"  # {}",
+            fixture.path.join("wasm-pack.toml").display()
+        )))
+        .stdout(predicate::str::contains(format!(
+            "update-check = false  # {}",
+            fixture.path.join("xdg/wasm-pack/config.toml").display()
+        )))
+        .stdout(predicate::str::contains("log-level = \"info\"  # default"))
+        .stderr(predicate::str::contains(
+            "\"bogus\" is an unknown key and will be ignored.",
+        ));
+}
+
+#[test]
+fn checksums_can_be_pinned() {
+    let fixture = fixture::Fixture::new();
+    let digest = "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD";
+    fixture.file(
+        "wasm-pack.toml",
+        format!(
+            "[checksums]\n\"wasm-bindgen-0.2.92-x86_64-unknown-linux-musl.tar.gz\" = \"{}\"\n",
+            digest
+        ),
+    );
+    let config = Config::load_from(&fixture.path, None).unwrap();
+    assert_eq!(
+        config
+            .checksum("wasm-bindgen-0.2.92-x86_64-unknown-linux-musl.tar.gz")
+            .unwrap()
+            .value,
+        digest.to_ascii_lowercase()
+    );
+    assert!(config.checksum("wasm-opt.tar.gz").is_none());
+
+    fixture.file(
+        "wasm-pack.toml",
+        "[checksums]\n\"wasm-opt.tar.gz\" = \"not a digest\"\n",
+    );
+    let err = Config::load_from(&fixture.path, None).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("The checksum of wasm-opt.tar.gz in "));
+}
+
+#[test]
+#[cfg(unix)]
+fn configured_tools_are_run_instead_of_installed() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = fixture::Fixture::new();
+    fixture.file(
+        "nix/bin/cargo-generate",
+        "#!/bin/sh\necho \"$@\" > generated.txt\n",
+    );
+    let cargo_generate = fixture.path.join("nix/bin/cargo-generate");
+    std::fs::set_permissions(&cargo_generate, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CARGO_GENERATE", &cargo_generate)
+        .arg("new")
+        .arg("hello")
+        .arg("--mode")
+        .arg("no-install")
+        .assert()
+        .success();
+    let args = std::fs::read_to_string(fixture.path.join("generated.txt")).unwrap();
+    assert!(args.starts_with("generate --git "));
+    assert!(args.contains("--name hello"));
+
+    fixture
+        .wasm_pack()
+        .env(
+            "WASM_PACK_CARGO_GENERATE",
+            fixture.path.join("nix/bin/missing"),
+        )
+        .arg("new")
+        .arg("hello")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The cargo-generate set by environment variable WASM_PACK_CARGO_GENERATE doesn't exist",
+        ));
+}
+
+#[test]
+fn update_check_can_be_configured() {
+    let fixture = fixture::Fixture::new();
+    fixture.file(
+        "wasm-pack.toml",
+        r#"
+            update-check-url = "https://registry.example.com/api/v1/crates/wasm-pack"
+            update-check-interval = "7d"
+        "#,
+    );
+
+    let config = Config::load_from(&fixture.path, None).unwrap();
+    assert_eq!(
+        config.update_check_url().value,
+        "https://registry.example.com/api/v1/crates/wasm-pack"
+    );
+    assert_eq!(
+        config.update_check_interval().value,
+        std::time::Duration::from_secs(7 * 24 * 60 * 60)
+    );
+
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_NO_UPDATE_CHECK", "1")
+        .arg("config")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "update-check = false  # environment variable WASM_PACK_NO_UPDATE_CHECK",
+        ))
+        .stdout(predicate::str::contains(format!(
+            "update-check-interval = \"1w\"  # {}",
+            fixture.path.join("wasm-pack.toml").display()
+        )));
+
+    fixture.file("wasm-pack.toml", "update-check-interval = \"soon\"\n");
+    let err = Config::load_from(&fixture.path, None).unwrap_err();
+    assert!(err.to_string().contains("for update-check-interval in"));
+}
+
+#[test]
+#[cfg(unix)]
+fn configured_tools_keep_their_file_name() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file(
+            "nix/bin/cargo-generate-0.18",
+            "#!/bin/sh\necho \"$@\" > generated.txt\n",
+        )
+        .file("nix/bin/cargo-generate", "#!/bin/sh\nexit 1\n");
+    for name in ["cargo-generate-0.18", "cargo-generate"] {
+        let path = fixture.path.join("nix/bin").join(name);
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fixture
+        .wasm_pack()
+        .env(
+            "WASM_PACK_CARGO_GENERATE",
+            fixture.path.join("nix/bin/cargo-generate-0.18"),
+        )
+        .arg("new")
+        .arg("hello")
+        .arg("--mode")
+        .arg("no-install")
+        .assert()
+        .success();
+    let args = std::fs::read_to_string(fixture.path.join("generated.txt")).unwrap();
+    assert!(args.contains("--name hello"));
+}
+
+#[test]
+fn project_config_is_found_from_the_crate_path() {
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file("wasm-pack.toml", "npm-client = \"pnpm\"\n")
+        .file("other/wasm-pack.toml", "log-level = \"loud\"\n");
+
+    fixture
+        .wasm_pack()
+        .arg("pack")
+        .arg("other")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown log-level: loud"));
+}
+
+#[test]
+fn invalid_config_is_reported_by_config_list_and_doctor() {
+    let fixture = fixture::Fixture::new();
+    fixture.file("wasm-pack.toml", "log-level = \"loud\"\n");
+
+    fixture
+        .wasm_pack()
+        .arg("config")
+        .arg("list")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("npm-client = \"npm\"  # default"))
+        .stderr(predicate::str::contains("Unknown log-level: loud"));
+
+    fixture
+        .wasm_pack()
+        .arg("doctor")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "config: Unknown log-level: loud in {}",
+            fixture.path.join("wasm-pack.toml").display()
+        )));
+}
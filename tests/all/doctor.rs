@@ -0,0 +1,87 @@
+use crate::utils::fixture;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+#[test]
+fn doctor_reports_checks_as_json() {
+    let fixture = fixture::Fixture::new();
+    fixture.file("README.md", "# Not a crate\n");
+    fixture
+        .wasm_pack()
+        .arg("--message-format")
+        .arg("json")
+        .arg("doctor")
+        .arg(&fixture.path)
+        .assert()
+        .stdout(predicate::str::contains("{\"event\":\"doctor-report\""))
+        .stdout(predicate::str::contains(
+            "{\"name\":\"rustc\",\"status\":\"pass\"",
+        ))
+        .stdout(predicate::str::contains(
+            "{\"name\":\"wasm-bindgen\",\"status\":\"warn\",\
+             \"message\":\"Not in a crate, so the wasm-bindgen version couldn't be checked.\"",
+        ));
+}
+
+#[test]
+fn doctor_suggests_a_fix_for_a_missing_driver() {
+    let fixture = fixture::Fixture::new();
+    fixture.file("wasm-pack.toml", "geckodriver = \"missing/geckodriver\"\n");
+    fixture
+        .wasm_pack()
+        .arg("doctor")
+        .arg(&fixture.path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "geckodriver: The configured geckodriver doesn't exist: {}",
+            fixture.path.join("missing/geckodriver").display()
+        )))
+        .stderr(predicate::str::contains(
+            "Fix: Fix the `geckodriver` setting in `wasm-pack config list`.",
+        ));
+}
+
+#[test]
+#[cfg(unix)]
+fn doctor_checks_the_version_of_a_configured_wasm_opt() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [lib]
+                crate-type = ["cdylib"]
+
+                [package.metadata.wasm-pack]
+                wasm-opt-version = "version_119"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "bin/wasm-opt",
+            "#!/bin/sh\necho 'wasm-opt version 117 (version_117)'\n",
+        );
+    let wasm_opt = fixture.path.join("bin/wasm-opt");
+    std::fs::set_permissions(&wasm_opt, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_WASM_OPT", &wasm_opt)
+        .arg("doctor")
+        .arg(&fixture.path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "wasm-opt: The wasm-opt set by environment variable WASM_PACK_WASM_OPT is \
+             version_117, but version_119 is required: {}",
+            wasm_opt.display()
+        )))
+        .stderr(predicate::str::contains("unset WASM_PACK_WASM_OPT."));
+}
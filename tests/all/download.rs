@@ -1,3 +1,5 @@
+use crate::utils;
+use assert_cmd::prelude::*;
 use wasm_pack::install::{self, Arch, Os, Tool};
 
 #[test]
@@ -74,6 +76,18 @@ fn can_download_prebuilt_wasm_opt() {
     }
 }
 
+#[test]
+fn wasm_opt_urls_use_the_pinned_version() {
+    let pinned =
+        install::prebuilt_url_for(&Tool::WasmOpt, "version_119", &Arch::X86_64, &Os::Linux)
+            .unwrap();
+    assert!(pinned.contains("/version_119/binaryen-version_119-"));
+
+    let latest =
+        install::prebuilt_url_for(&Tool::WasmOpt, "latest", &Arch::X86_64, &Os::Linux).unwrap();
+    assert!(latest.contains(install::DEFAULT_WASM_OPT_VERSION));
+}
+
 #[test]
 fn all_latest_tool_download_urls_valid() {
     let mut errors = Vec::new();
@@ -107,3 +121,235 @@ fn all_latest_tool_download_urls_valid() {
         );
     }
 }
+
+/// A `.tar.gz` archive with an executable `name` in a `bin` directory.
+fn tarball_with_binary(name: &str) -> Vec<u8> {
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    let contents = b"#!/bin/sh\n";
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o755);
+    builder
+        .append_data(&mut header, format!("bin/{}", name), &contents[..])
+        .unwrap();
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+#[test]
+fn can_download_from_a_file_url() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let archive = dir.path().join("tool-1.0.0.tar.gz");
+    std::fs::write(&archive, tarball_with_binary("tool")).unwrap();
+    let url = format!("file://{}", archive.display());
+    let cache = binary_install::Cache::at(&dir.path().join("cache"));
+
+    assert!(
+        install::download::download(&cache, false, "tool", &["tool"], &url)
+            .unwrap()
+            .is_none()
+    );
+    let download = install::download::download(&cache, true, "tool", &["tool"], &url)
+        .unwrap()
+        .unwrap();
+    assert!(download.path().join("tool").is_file());
+
+    // The entry is where `binary_install` looks for it too.
+    let cached = cache.download(false, "tool", &["tool"], &url).unwrap();
+    assert_eq!(cached.unwrap().path(), download.path());
+}
+
+#[test]
+fn downloading_from_a_file_url_reports_missing_binaries() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let archive = dir.path().join("tool.tar.gz");
+    std::fs::write(&archive, tarball_with_binary("other")).unwrap();
+    let url = format!("file://{}", archive.display());
+    let cache = binary_install::Cache::at(&dir.path().join("cache"));
+
+    let error = install::download::download(&cache, true, "tool", &["tool"], &url).unwrap_err();
+    assert!(error
+        .chain()
+        .any(|e| e.to_string() == "the tarball was missing expected executables: tool"));
+    let error = install::download::download(
+        &cache,
+        true,
+        "tool",
+        &["tool"],
+        &format!("file://{}", dir.path().join("missing.tar.gz").display()),
+    )
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("failed to download from file://"));
+}
+
+#[test]
+fn mirrors_can_be_set_with_environment_variables() {
+    assert_eq!(
+        wasm_pack::config::mirror_env_var("wasm-bindgen"),
+        "WASM_PACK_MIRROR_WASM_BINDGEN"
+    );
+
+    // The variable is only set for the child process, so that it doesn't leak
+    // into the other tests of this process.
+    let fixture = utils::fixture::Fixture::new();
+    fixture.file(
+        "wasm-pack.toml",
+        "[mirrors]\nmirror-test-tool = \"https://example.com/{tool}/{version}/{target}.tar.gz\"\n",
+    );
+    fixture
+        .wasm_pack()
+        .env(
+            "WASM_PACK_MIRROR_MIRROR_TEST_TOOL",
+            "file:///mirror/{tool}/{version}/{tool}-{target}.tar.gz",
+        )
+        .arg("config")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "mirrors.mirror-test-tool = \"file:///mirror/{tool}/{version}/{tool}-{target}.tar.gz\"  \
+             # environment variable WASM_PACK_MIRROR_MIRROR_TEST_TOOL",
+        ));
+
+    let config = wasm_pack::config::Config::load_from(&fixture.path, None).unwrap();
+    assert_eq!(
+        config
+            .mirror_url("mirror-test-tool", "1.2.3", "x86_64-linux")
+            .unwrap(),
+        "https://example.com/mirror-test-tool/1.2.3/x86_64-linux.tar.gz"
+    );
+    assert!(config
+        .mirror_url("another-test-tool", "1.2.3", "x86_64-linux")
+        .is_none());
+}
+
+#[test]
+fn downloads_are_verified_against_published_digests() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let tarball = tarball_with_binary("tool");
+    let digest = install::download::sha256(&tarball);
+    let cache = binary_install::Cache::at(&dir.path().join("cache"));
+
+    let good = dir.path().join("good.tar.gz");
+    std::fs::write(&good, &tarball).unwrap();
+    std::fs::write(
+        dir.path().join("good.tar.gz.sha256sum"),
+        format!("{}  good.tar.gz\n", digest),
+    )
+    .unwrap();
+    let url = format!("file://{}", good.display());
+    assert!(
+        install::download::download(&cache, true, "tool", &["tool"], &url)
+            .unwrap()
+            .is_some()
+    );
+
+    let bad = dir.path().join("bad.tar.gz");
+    std::fs::write(&bad, &tarball).unwrap();
+    std::fs::write(dir.path().join("bad.tar.gz.sha256"), "0".repeat(64)).unwrap();
+    let url = format!("file://{}", bad.display());
+    let error = install::download::download(&cache, true, "tool", &["tool"], &url).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "The SHA-256 digest of {} is {}, but {} was expected (published at {}.sha256). \
+             The download may be corrupted or tampered with, so it was not extracted.",
+            url,
+            digest,
+            "0".repeat(64),
+            url
+        )
+    );
+    assert!(cache
+        .download(false, "tool", &["tool"], &url)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn mirrored_downloads_must_have_a_digest() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let tarball = tarball_with_binary("tool");
+    let url = format!("file://{}", dir.path().join("tool.tar.gz").display());
+    let mirror = wasm_pack::config::Source::Env("WASM_PACK_MIRROR_TOOL".to_string());
+
+    // Without a mirror, an archive without a digest is only warned about.
+    assert!(install::download::verify("tool", &url, &tarball, None).is_ok());
+
+    let error = install::download::verify("tool", &url, &tarball, Some(&mirror)).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with(&format!("No SHA-256 digest is published for {}", url)));
+
+    std::fs::write(
+        dir.path().join("tool.tar.gz.sha256"),
+        install::download::sha256(&tarball),
+    )
+    .unwrap();
+    assert!(install::download::verify("tool", &url, &tarball, Some(&mirror)).is_ok());
+}
+
+#[test]
+fn digests_are_only_looked_up_for_tools_that_publish_them() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let tarball = tarball_with_binary("chromedriver");
+    let archive = dir.path().join("chromedriver.zip");
+    std::fs::write(dir.path().join("chromedriver.zip.sha256"), "0".repeat(64)).unwrap();
+    let url = format!("file://{}", archive.display());
+
+    assert!(install::download::verify("chromedriver", &url, &tarball, None).is_ok());
+    assert!(install::download::verify("cargo-generate", &url, &tarball, None).is_ok());
+    assert!(install::download::verify("wasm-opt", &url, &tarball, None).is_err());
+
+    // Mirrors may publish digests for any tool.
+    let mirror = wasm_pack::config::Source::Env("WASM_PACK_MIRROR_CHROMEDRIVER".to_string());
+    assert!(install::download::verify("chromedriver", &url, &tarball, Some(&mirror)).is_err());
+}
+
+#[test]
+fn sha256_digests_are_lowercase_hex() {
+    assert_eq!(
+        install::download::sha256(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[test]
+fn cargo_installed_wasm_opt_is_found_by_version() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let cache = binary_install::Cache::at(dir.path());
+    let entry = dir.path().join("wasm-opt-cargo-install-version_119");
+    std::fs::create_dir_all(entry.join("bin")).unwrap();
+    std::fs::write(entry.join("bin/wasm-opt"), "").unwrap();
+
+    match install::cargo_install(Tool::WasmOpt, &cache, "version_119", false).unwrap() {
+        install::Status::Found(dl) => assert_eq!(dl.path(), entry),
+        _ => panic!("the cargo-installed wasm-opt wasn't found"),
+    }
+    assert!(matches!(
+        install::cargo_install(Tool::WasmOpt, &cache, "version_118", false).unwrap(),
+        install::Status::CannotInstall
+    ));
+}
+
+#[test]
+fn wasm_opt_is_cargo_installed_from_a_published_crate_version() {
+    let version = |version| install::cargo_install_version(&Tool::WasmOpt, version);
+    assert_eq!(version("version_116").unwrap(), Some("~0.116".to_string()));
+    assert_eq!(version("version_110").unwrap(), Some("~0.110".to_string()));
+    // There's no crate release after 0.116.
+    assert_eq!(version("version_117").unwrap(), Some("~0.116".to_string()));
+    assert_eq!(version("version_123").unwrap(), Some("~0.116".to_string()));
+    assert!(version("version_115").is_err());
+    assert!(version("version_105").is_err());
+    assert!(version("version_x").is_err());
+
+    let version = install::cargo_install_version(&Tool::WasmBindgen, "0.2.92").unwrap();
+    assert_eq!(version, Some("0.2.92".to_string()));
+    let version = install::cargo_install_version(&Tool::CargoGenerate, "latest").unwrap();
+    assert_eq!(version, None);
+}
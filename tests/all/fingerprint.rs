@@ -0,0 +1,82 @@
+use crate::utils::fixture;
+use wasm_pack::command::build::Target;
+use wasm_pack::fingerprint::{Fingerprints, Inputs, Record};
+
+fn fingerprint(
+    wasm: &std::path::Path,
+    flags: &[String],
+    wasm_opt_args: Option<&[String]>,
+) -> String {
+    Inputs {
+        wasm,
+        wasm_bindgen_version: "0.2.92",
+        wasm_bindgen_flags: flags,
+        wasm_opt_args,
+        wasm_opt_version: None,
+        target: Target::Web,
+    }
+    .fingerprint()
+    .unwrap()
+}
+
+#[test]
+fn fingerprint_changes_with_any_input() {
+    let fixture = fixture::Fixture::new();
+    fixture.file("module.wasm", b"\0asm one");
+    let wasm = fixture.path.join("module.wasm");
+    let flags = vec!["--typescript".to_string()];
+    let opt_args = vec!["-O".to_string()];
+
+    let original = fingerprint(&wasm, &flags, Some(&opt_args));
+    assert_eq!(original, fingerprint(&wasm, &flags, Some(&opt_args)));
+
+    assert_ne!(original, fingerprint(&wasm, &flags, None));
+    assert_ne!(original, fingerprint(&wasm, &[], Some(&opt_args)));
+    assert_ne!(
+        original,
+        fingerprint(&wasm, &flags, Some(&["-O4".to_string()]))
+    );
+    let other_target = Inputs {
+        wasm: &wasm,
+        wasm_bindgen_version: "0.2.92",
+        wasm_bindgen_flags: &flags,
+        wasm_opt_args: Some(&opt_args),
+        wasm_opt_version: None,
+        target: Target::Nodejs,
+    };
+    assert_ne!(original, other_target.fingerprint().unwrap());
+    let pinned_wasm_opt = Inputs {
+        wasm: &wasm,
+        wasm_bindgen_version: "0.2.92",
+        wasm_bindgen_flags: &flags,
+        wasm_opt_args: Some(&opt_args),
+        wasm_opt_version: Some("version_119"),
+        target: Target::Web,
+    };
+    assert_ne!(original, pinned_wasm_opt.fingerprint().unwrap());
+
+    fixture.file("module.wasm", b"\0asm two");
+    assert_ne!(original, fingerprint(&wasm, &flags, Some(&opt_args)));
+}
+
+#[test]
+fn fingerprints_round_trip_through_the_out_dir() {
+    let fixture = fixture::Fixture::new();
+    fixture.file("pkg/index.js", "");
+    let out_dir = fixture.path.join("pkg");
+    assert!(Fingerprints::load(&out_dir).get(Target::Web).is_none());
+
+    let record = Record {
+        fingerprint: "abc".to_string(),
+        npm_dependencies: Some([("foo".to_string(), "^1.0.0".to_string())].into()),
+        outputs: vec!["index.js".into()],
+    };
+    let mut fingerprints = Fingerprints::default();
+    fingerprints.insert(Target::Web, record.clone());
+    fingerprints.save(&out_dir).unwrap();
+
+    let mut loaded = Fingerprints::load(&out_dir);
+    assert_eq!(loaded.get(Target::Web), Some(&record));
+    assert!(loaded.get(Target::Nodejs).is_none());
+    assert_eq!(loaded.remove(Target::Web), Some(record));
+}
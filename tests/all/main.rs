@@ -13,15 +13,23 @@ extern crate tempfile;
 extern crate wasm_pack;
 
 mod build;
+mod cache;
+mod config;
+mod doctor;
 mod download;
+mod fingerprint;
 mod generate;
 mod license;
 mod lockfile;
 mod log_level;
 mod manifest;
+mod message_format;
 mod readme;
+mod self_command;
+mod size;
 mod stamps;
 mod test;
 mod utils;
 mod wasm_opt;
+mod watch;
 mod webdriver;
@@ -1,9 +1,10 @@
 use crate::utils::{self, fixture};
 use assert_cmd::prelude::*;
+use predicates::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
-use wasm_pack::command::build::Target;
+use wasm_pack::command::build::{BuildOptions, BuildProfile, Target};
 use wasm_pack::command::utils::get_crate_path;
 use wasm_pack::{self, emoji, license, manifest};
 
@@ -79,7 +80,7 @@ fn it_creates_a_package_json_default_path() {
     let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &None, false, Target::Bundler)
+        .write_package_json(&out_dir, &None, false, &[Target::Bundler])
         .is_ok());
     let package_json_path = &fixture.path.join("pkg").join("package.json");
     fs::metadata(package_json_path).unwrap();
@@ -119,7 +120,7 @@ fn it_creates_a_package_json_provided_path() {
     let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &None, false, Target::Bundler)
+        .write_package_json(&out_dir, &None, false, &[Target::Bundler])
         .is_ok());
     let package_json_path = &fixture.path.join("pkg").join("package.json");
     fs::metadata(package_json_path).unwrap();
@@ -149,7 +150,12 @@ fn it_creates_a_package_json_provided_path_with_scope() {
     let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &Some("test".to_string()), false, Target::Bundler,)
+        .write_package_json(
+            &out_dir,
+            &Some("test".to_string()),
+            false,
+            &[Target::Bundler],
+        )
         .is_ok());
     let package_json_path = &fixture.path.join("pkg").join("package.json");
     fs::metadata(package_json_path).unwrap();
@@ -179,7 +185,7 @@ fn it_creates_a_pkg_json_with_correct_files_on_node() {
     let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &None, false, Target::Nodejs)
+        .write_package_json(&out_dir, &None, false, &[Target::Nodejs])
         .is_ok());
     let package_json_path = &out_dir.join("package.json");
     fs::metadata(package_json_path).unwrap();
@@ -213,7 +219,7 @@ fn it_creates_a_pkg_json_with_correct_files_on_nomodules() {
     let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &None, false, Target::NoModules)
+        .write_package_json(&out_dir, &None, false, &[Target::NoModules])
         .is_ok());
     let package_json_path = &out_dir.join("package.json");
     fs::metadata(package_json_path).unwrap();
@@ -247,7 +253,7 @@ fn it_creates_a_package_json_with_correct_files_when_out_name_is_provided() {
     let crate_data = manifest::CrateData::new(&fixture.path, Some("index".to_owned())).unwrap();
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &None, false, Target::Bundler)
+        .write_package_json(&out_dir, &None, false, &[Target::Bundler])
         .is_ok());
     let package_json_path = &fixture.path.join("pkg").join("package.json");
     fs::metadata(package_json_path).unwrap();
@@ -280,7 +286,7 @@ fn it_creates_a_pkg_json_in_out_dir() {
     let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &None, false, Target::Bundler)
+        .write_package_json(&out_dir, &None, false, &[Target::Bundler])
         .is_ok());
 
     let package_json_path = &fixture.path.join(&out_dir).join("package.json");
@@ -295,7 +301,7 @@ fn it_creates_a_package_json_with_correct_keys_when_types_are_skipped() {
     let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &None, true, Target::Bundler)
+        .write_package_json(&out_dir, &None, true, &[Target::Bundler])
         .is_ok());
     let package_json_path = &out_dir.join("package.json");
     fs::metadata(package_json_path).unwrap();
@@ -337,7 +343,7 @@ fn it_creates_a_package_json_with_npm_dependencies_provided_by_wasm_bindgen() {
     )
     .unwrap();
     assert!(crate_data
-        .write_package_json(&out_dir, &None, true, Target::Bundler)
+        .write_package_json(&out_dir, &None, true, &[Target::Bundler])
         .is_ok());
     let package_json_path = &out_dir.join("package.json");
     fs::metadata(package_json_path).unwrap();
@@ -370,6 +376,110 @@ fn it_creates_a_package_json_with_npm_dependencies_provided_by_wasm_bindgen() {
     assert_eq!(dependencies.unwrap(), expected_dependencies);
 }
 
+#[test]
+fn it_creates_a_package_json_with_exports_for_multiple_targets() {
+    let fixture = fixture::js_hello_world();
+    let out_dir = fixture.path.join("pkg");
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+    wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
+    for target in &["bundler", "nodejs", "web"] {
+        fs::create_dir_all(out_dir.join(target)).unwrap();
+    }
+    // Write a `package.json` in one target's directory, as wasm-bindgen does:
+    utils::manifest::create_wbg_package_json(&out_dir.join("web"), r#"{ "foo": "^1.2.3" }"#)
+        .unwrap();
+    crate_data
+        .write_package_json(
+            &out_dir,
+            &None,
+            false,
+            &[Target::Bundler, Target::Nodejs, Target::Web],
+        )
+        .unwrap();
+
+    let pkg = utils::manifest::read_package_json(&fixture.path, &out_dir).unwrap();
+    assert_eq!(pkg.name, "js-hello-world");
+    assert_eq!(pkg.ty, "");
+    assert_eq!(pkg.main, "./nodejs/js_hello_world.js");
+    assert_eq!(pkg.module, "./bundler/js_hello_world.js");
+    assert_eq!(pkg.types, "./bundler/js_hello_world.d.ts");
+    assert_eq!(pkg.files, vec!["bundler/", "nodejs/", "web/"]);
+
+    let exports = &pkg.exports["."];
+    assert_eq!(exports["types"], "./bundler/js_hello_world.d.ts");
+    assert_eq!(exports["import"], "./bundler/js_hello_world.js");
+    assert_eq!(exports["require"], "./nodejs/js_hello_world.js");
+    assert_eq!(exports["browser"], "./web/js_hello_world.js");
+    assert_eq!(exports["node"], "./nodejs/js_hello_world.js");
+    assert_eq!(exports["default"], "./bundler/js_hello_world.js");
+
+    // Resolvers pick the first condition that matches, so the order of the
+    // keys matters: bundlers match `browser` too, and must get the bundler
+    // output from `import`.
+    let json = fs::read_to_string(out_dir.join("package.json")).unwrap();
+    let exports = &json[json.find("\"exports\"").unwrap()..];
+    let positions: Vec<usize> = ["types", "node", "import", "browser", "require", "default"]
+        .iter()
+        .map(|key| exports.find(&format!("\"{}\":", key)).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let mut expected_dependencies: HashMap<String, String> = HashMap::new();
+    expected_dependencies.insert("foo".to_owned(), "^1.2.3".to_owned());
+    assert_eq!(pkg.dependencies.unwrap(), expected_dependencies);
+
+    let nodejs = utils::manifest::read_package_json_value(&out_dir.join("nodejs")).unwrap();
+    assert_eq!(nodejs["type"], "commonjs");
+    let web = utils::manifest::read_package_json_value(&out_dir.join("web")).unwrap();
+    assert_eq!(web["type"], "module");
+}
+
+#[test]
+fn it_points_types_and_main_at_a_module_target_of_a_multi_target_package() {
+    let fixture = fixture::js_hello_world();
+    let out_dir = fixture.path.join("pkg");
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+    wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
+    for target in &["no-modules", "web"] {
+        fs::create_dir_all(out_dir.join(target)).unwrap();
+    }
+    crate_data
+        .write_package_json(&out_dir, &None, false, &[Target::NoModules, Target::Web])
+        .unwrap();
+
+    let pkg = utils::manifest::read_package_json(&fixture.path, &out_dir).unwrap();
+    assert_eq!(pkg.main, "./web/js_hello_world.js");
+    assert_eq!(pkg.types, "./web/js_hello_world.d.ts");
+    assert_eq!(pkg.exports["."]["types"], "./web/js_hello_world.d.ts");
+}
+
+#[test]
+fn it_finds_the_cdylib_members_of_a_workspace() {
+    let fixture = fixture::workspace_with_cdylib_members();
+
+    let members = manifest::CrateData::workspace_members(&fixture.path, &[], None).unwrap();
+    let names: Vec<&str> = members.iter().map(|m| m.package_name()).collect();
+    assert_eq!(names, vec!["first", "second"]);
+    assert!(members[0]
+        .manifest_directory()
+        .ends_with(PathBuf::from("first")));
+
+    let members =
+        manifest::CrateData::workspace_members(&fixture.path, &["second".to_string()], None)
+            .unwrap();
+    let names: Vec<&str> = members.iter().map(|m| m.package_name()).collect();
+    assert_eq!(names, vec!["second"]);
+
+    assert!(
+        manifest::CrateData::workspace_members(&fixture.path, &["helper".to_string()], None)
+            .is_err()
+    );
+    assert!(
+        manifest::CrateData::workspace_members(&fixture.path, &["missing".to_string()], None)
+            .is_err()
+    );
+}
+
 #[test]
 fn it_errors_when_wasm_bindgen_is_not_declared() {
     let fixture = fixture::bad_cargo_toml();
@@ -409,7 +519,7 @@ fn it_sets_homepage_field_if_available_in_cargo_toml() {
 
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     crate_data
-        .write_package_json(&out_dir, &None, true, Target::Bundler)
+        .write_package_json(&out_dir, &None, true, &[Target::Bundler])
         .unwrap();
 
     let pkg = utils::manifest::read_package_json(&fixture.path, &out_dir).unwrap();
@@ -425,7 +535,7 @@ fn it_sets_homepage_field_if_available_in_cargo_toml() {
 
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     crate_data
-        .write_package_json(&out_dir, &None, true, Target::Bundler)
+        .write_package_json(&out_dir, &None, true, &[Target::Bundler])
         .unwrap();
 
     let pkg = utils::manifest::read_package_json(&fixture.path, &out_dir).unwrap();
@@ -464,7 +574,7 @@ fn it_sets_keywords_field_if_available_in_cargo_toml() {
 
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     crate_data
-        .write_package_json(&out_dir, &None, true, Target::Bundler)
+        .write_package_json(&out_dir, &None, true, &[Target::Bundler])
         .unwrap();
 
     let pkg = utils::manifest::read_package_json(&fixture.path, &out_dir).unwrap();
@@ -482,7 +592,7 @@ fn it_sets_keywords_field_if_available_in_cargo_toml() {
 
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     crate_data
-        .write_package_json(&out_dir, &None, true, Target::Bundler)
+        .write_package_json(&out_dir, &None, true, &[Target::Bundler])
         .unwrap();
 
     let pkg = utils::manifest::read_package_json(&fixture.path, &out_dir).unwrap();
@@ -530,6 +640,336 @@ fn configure_wasm_bindgen_debug_incorrectly_is_error() {
         .failure();
 }
 
+#[test]
+fn it_reads_threads_from_the_profile_metadata() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack.profile.release]
+            threads = true
+            "#,
+    );
+    fixture.cargo_check();
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+
+    assert!(crate_data
+        .configured_profile(BuildProfile::Release)
+        .threads());
+    assert!(!crate_data.configured_profile(BuildProfile::Dev).threads());
+}
+
+#[test]
+fn it_looks_up_custom_profiles_by_name() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [profile.staging]
+            inherits = "release"
+
+            [profile.fast-dev]
+            inherits = "dev"
+
+            [profile.faster-dev]
+            inherits = "fast-dev"
+
+            [package.metadata.wasm-pack.profile.staging]
+            wasm-opt = ["-Os"]
+
+            [package.metadata.wasm-pack.profile.staging.wasm-bindgen]
+            debug-js-glue = true
+            "#,
+    );
+    fixture.cargo_check();
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+
+    let staging = crate_data.configured_profile(BuildProfile::Custom("staging".into()));
+    assert!(staging.wasm_bindgen_debug_js_glue());
+    assert_eq!(staging.wasm_opt_args(), Some(vec!["-Os".to_string()]));
+
+    // Profiles without a table of their own follow cargo's `inherits` chain.
+    for name in ["fast-dev", "faster-dev"] {
+        let profile = crate_data.configured_profile(BuildProfile::Custom(name.into()));
+        assert!(profile.wasm_bindgen_debug_js_glue());
+        assert_eq!(profile.wasm_opt_args(), None);
+    }
+}
+
+#[test]
+fn it_only_uses_the_custom_profile_table_for_the_custom_profile() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [profile.staging]
+            inherits = "dev"
+
+            [profile.custom]
+            inherits = "release"
+
+            [package.metadata.wasm-pack.profile.custom]
+            wasm-opt = false
+
+            [package.metadata.wasm-pack.profile.custom.wasm-bindgen]
+            debug-js-glue = true
+            "#,
+    );
+    fixture.cargo_check();
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+
+    let custom = crate_data.configured_profile(BuildProfile::Custom("custom".into()));
+    assert_eq!(custom.wasm_opt_args(), None);
+    assert!(custom.wasm_bindgen_debug_js_glue());
+
+    // Other profiles keep the defaults they inherit.
+    let staging = crate_data.configured_profile(BuildProfile::Custom("staging".into()));
+    assert!(staging.wasm_bindgen_debug_js_glue());
+    let release = crate_data.configured_profile(BuildProfile::Release);
+    assert_eq!(release.wasm_opt_args(), Some(vec!["-O".to_string()]));
+    assert!(!release.wasm_bindgen_debug_js_glue());
+}
+
+#[test]
+fn it_warns_about_profile_tables_cargo_does_not_define() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [profile.staging]
+            inherits = "release"
+
+            [package.metadata.wasm-pack.profile.relase]
+            wasm-opt = false
+
+            [package.metadata.wasm-pack.profile.staging]
+            wasm-opt = false
+            "#,
+    );
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--mode")
+        .arg("no-install")
+        .assert()
+        .stderr(
+            predicates::str::contains("\"profile.relase\" is not a cargo profile")
+                .and(predicates::str::contains("\"profile.staging\"").not()),
+        );
+}
+
+#[test]
+fn it_reads_build_defaults_from_the_package_and_workspace_metadata() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["blah"]
+
+                [workspace.metadata.wasm-pack]
+                scope = "acme"
+                out-dir = "../web/pkg"
+                no-typescript = true
+                wasm-opt-version = "version_119"
+                bogus = 1
+            "#,
+        )
+        .file(
+            "blah/Cargo.toml",
+            r#"
+                [package]
+                authors = ["The wasm-pack developers"]
+                description = "so awesome rust+wasm package"
+                license = "WTFPL"
+                name = "blah"
+                repository = "https://github.com/rustwasm/wasm-pack.git"
+                version = "0.1.0"
+
+                [lib]
+                crate-type = ["cdylib"]
+
+                [package.metadata.wasm-pack]
+                target = "web,nodejs"
+                out-dir = "pkg-{name}"
+                cargo-args = ["--features", "fast"]
+            "#,
+        )
+        .file("blah/src/lib.rs", "");
+    let crate_data = manifest::CrateData::new(&fixture.path.join("blah"), None).unwrap();
+    let defaults = crate_data.build_defaults();
+
+    assert_eq!(defaults.target, Some(vec![Target::Web, Target::Nodejs]));
+    assert_eq!(defaults.scope.as_deref(), Some("acme"));
+    assert_eq!(defaults.out_dir.as_deref(), Some("pkg-{name}"));
+    assert_eq!(defaults.no_typescript, Some(true));
+    assert_eq!(defaults.wasm_opt_version.as_deref(), Some("version_119"));
+
+    let opts = BuildOptions {
+        target: vec![Target::Deno],
+        extra_options: vec!["--offline".to_string()],
+        ..Default::default()
+    }
+    .with_defaults(defaults);
+    assert_eq!(opts.target, vec![Target::Deno]);
+    assert_eq!(opts.scope.as_deref(), Some("acme"));
+    assert_eq!(opts.disable_dts, Some(true));
+    assert_eq!(opts.extra_options, ["--features", "fast", "--offline"]);
+
+    // Flags given on the command line override the metadata.
+    let opts = BuildOptions {
+        disable_dts: Some(false),
+        ..Default::default()
+    }
+    .with_defaults(defaults);
+    assert_eq!(opts.disable_dts, Some(false));
+}
+
+#[test]
+fn custom_rust_targets_are_relative_to_the_manifest_that_sets_them() {
+    let fixture = utils::fixture::Fixture::new();
+    let member = |name: &str, metadata: &str| {
+        format!(
+            r#"
+                [package]
+                authors = ["The wasm-pack developers"]
+                name = "{}"
+                version = "0.1.0"
+
+                [lib]
+                crate-type = ["cdylib"]
+
+                {}
+            "#,
+            name, metadata
+        )
+    };
+    fixture
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["blah", "other"]
+
+                [workspace.metadata.wasm-pack]
+                rust-target = "targets/shared.json"
+            "#,
+        )
+        .file(
+            "blah/Cargo.toml",
+            member(
+                "blah",
+                "[package.metadata.wasm-pack]\nrust-target = \"own.json\"",
+            ),
+        )
+        .file("blah/src/lib.rs", "")
+        .file("other/Cargo.toml", member("other", ""))
+        .file("other/src/lib.rs", "");
+
+    let rust_target = |name: &str| {
+        let crate_data = manifest::CrateData::new(&fixture.path.join(name), None).unwrap();
+        PathBuf::from(crate_data.build_defaults().rust_target.clone().unwrap())
+    };
+    let root = fs::canonicalize(&fixture.path).unwrap();
+    let canonical = |path: PathBuf| {
+        fs::canonicalize(path.parent().unwrap())
+            .unwrap()
+            .join(path.file_name().unwrap())
+    };
+    assert_eq!(canonical(rust_target("blah")), root.join("blah/own.json"));
+    fs::create_dir_all(fixture.path.join("targets")).unwrap();
+    assert_eq!(
+        canonical(rust_target("other")),
+        root.join("targets/shared.json")
+    );
+}
+
+#[test]
+fn it_warns_about_unknown_keys_in_the_workspace_metadata() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [workspace.metadata.wasm-pack]
+            scop = "acme"
+            "#,
+    );
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--mode")
+        .arg("no-install")
+        .assert()
+        .stderr(predicates::str::contains(
+            "\"workspace.metadata.wasm-pack.scop\" is an unknown key and will be ignored.",
+        ));
+}
+
 #[test]
 fn parse_crate_data_returns_unused_keys_in_cargo_toml() {
     let fixture = utils::fixture::Fixture::new();
@@ -552,8 +992,8 @@ fn parse_crate_data_returns_unused_keys_in_cargo_toml() {
             [dependencies]
             wasm-bindgen = "0.2"
 
-            # Note: production is not valid.
-            [package.metadata.wasm-pack.profile.production.wasm-bindgen]
+            # Note: profiles is not valid.
+            [package.metadata.wasm-pack.profiles.production.wasm-bindgen]
             debug-js-glue = true
             "#,
         )
@@ -565,10 +1005,10 @@ fn parse_crate_data_returns_unused_keys_in_cargo_toml() {
         .assert()
         .success()
         .stderr(predicates::str::contains(format!(
-        "[WARN]: {} \"package.metadata.wasm-pack.profile.production\" is an unknown key and will \
+            "[WARN]: {} \"package.metadata.wasm-pack.profiles\" is an unknown key and will \
          be ignored. Please check your Cargo.toml.",
-        emoji::WARN
-    )));
+            emoji::WARN
+        )));
 }
 
 #[test]
@@ -581,7 +1021,7 @@ fn it_lists_license_files_in_files_field_of_package_json() {
     wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
     license::copy_from_crate(&crate_data, &fixture.path, &out_dir).unwrap();
     crate_data
-        .write_package_json(&out_dir, &None, false, Target::Bundler)
+        .write_package_json(&out_dir, &None, false, &[Target::Bundler])
         .unwrap();
 
     let package_json_path = &fixture.path.join("pkg").join("package.json");
@@ -643,3 +1083,61 @@ fn it_doesnt_recurse_up_the_path_to_find_cargo_toml_when_default() {
     let crate_data = manifest::CrateData::new(&path, None);
     assert!(crate_data.is_err());
 }
+
+#[test]
+fn it_warns_when_the_custom_profile_table_is_no_longer_used() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [profile.staging]
+            inherits = "release"
+
+            [package.metadata.wasm-pack.profile.custom]
+            wasm-opt = false
+            "#,
+    );
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--mode")
+        .arg("no-install")
+        .arg("--profile")
+        .arg("staging")
+        .assert()
+        .stderr(predicates::str::contains(
+            "[package.metadata.wasm-pack.profile.custom] is no longer used for `--profile staging`.",
+        ));
+}
+
+#[test]
+fn boolean_build_flags_can_be_turned_off_on_the_command_line() {
+    use clap::Parser;
+
+    let parse = |args: &[&str]| match wasm_pack::Cli::try_parse_from(args).unwrap().cmd {
+        wasm_pack::command::Command::Build(opts) => opts,
+        _ => unreachable!(),
+    };
+    let opts = parse(&[
+        "wasm-pack",
+        "build",
+        "--no-opt",
+        "--no-typescript=false",
+        "crate",
+    ]);
+    assert_eq!(opts.no_opt, Some(true));
+    assert_eq!(opts.disable_dts, Some(false));
+    assert_eq!(opts.no_pack, None);
+    assert_eq!(opts.path, Some(PathBuf::from("crate")));
+}
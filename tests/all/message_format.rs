@@ -0,0 +1,140 @@
+use crate::utils;
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate::str::contains;
+use serde_json::Value;
+
+fn events(stdout: &[u8]) -> Vec<Value> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("every line of stdout should be JSON"))
+        .collect()
+}
+
+#[test]
+fn json_reports_steps_and_a_failing_summary() {
+    let fixture = utils::fixture::no_cdylib();
+    let output = fixture
+        .wasm_pack()
+        .arg("--message-format")
+        .arg("json")
+        .arg("build")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("[INFO]"), "{}", stderr);
+    assert!(!stderr.contains("Error:"), "{}", stderr);
+
+    let events = events(&output.stdout);
+    let started: Vec<&Value> = events
+        .iter()
+        .filter(|event| event["event"] == "step-started")
+        .map(|event| &event["name"])
+        .collect();
+    assert_eq!(
+        started,
+        vec!["step_check_rustc_version", "step_check_crate_config"]
+    );
+
+    let failed = events
+        .iter()
+        .find(|event| event["event"] == "step-finished" && event["success"] == false)
+        .unwrap();
+    assert_eq!(failed["name"], "step_check_crate_config");
+    assert!(failed["duration_ms"].is_u64());
+
+    let summary = events.last().unwrap();
+    assert_eq!(summary["event"], "summary");
+    assert_eq!(summary["success"], false);
+    let message = summary["errors"][0]["message"].as_str().unwrap();
+    assert!(message.contains("crate-type must be cdylib"), "{}", message);
+}
+
+#[test]
+fn unknown_message_format_is_rejected() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("--message-format")
+        .arg("xml")
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(contains("Unknown message-format: xml"));
+}
+
+#[test]
+fn json_lists_the_config_and_the_cache_as_events() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.file("wasm-pack.toml", "npm-client = \"pnpm\"\n");
+    let cache = fixture.path.join("cache");
+    std::fs::create_dir_all(cache.join("wasm-opt-0123456789abcdef")).unwrap();
+
+    let run = |args: &[&str]| {
+        let output = fixture
+            .wasm_pack()
+            .env("WASM_PACK_CACHE", &cache)
+            .arg("--message-format")
+            .arg("json")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        events(&output.stdout)
+    };
+
+    let events = run(&["config", "list"]);
+    assert_eq!(events[0]["event"], "config-list");
+    let npm_client = events[0]["settings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|setting| setting["key"] == "npm-client")
+        .unwrap();
+    assert_eq!(npm_client["value"], "\"pnpm\"");
+    assert_eq!(
+        npm_client["source"],
+        fixture.path.join("wasm-pack.toml").display().to_string()
+    );
+
+    let events = run(&["cache", "path"]);
+    assert_eq!(events[0]["event"], "cache-path");
+    assert_eq!(events[0]["path"], cache.display().to_string());
+
+    let events = run(&["cache", "list"]);
+    assert_eq!(events[0]["event"], "cache-list");
+    assert_eq!(events[0]["entries"][0]["tool"], "wasm-opt");
+    assert_eq!(events[0]["entries"][0]["source"], "download");
+    assert!(events[0]["entries"][0]["last_used"].is_u64());
+}
+
+#[test]
+#[cfg(unix)]
+fn json_keeps_the_output_of_child_processes_off_stdout() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .file("pkg/package.json", "{}")
+        .file("bin/npm", "#!/bin/sh\necho \"npm $1 output\"\n");
+    let npm = fixture.path.join("bin/npm");
+    std::fs::set_permissions(&npm, std::fs::Permissions::from_mode(0o755)).unwrap();
+    fixture.file(
+        "wasm-pack.toml",
+        format!("npm-client = {:?}\n", npm.display().to_string()),
+    );
+
+    let output = fixture
+        .wasm_pack()
+        .arg("--message-format")
+        .arg("json")
+        .arg("pack")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("npm pack output"), "{}", stderr);
+    let summary = events(&output.stdout).pop().unwrap();
+    assert_eq!(summary["event"], "summary");
+    assert_eq!(summary["success"], true);
+}
@@ -0,0 +1,91 @@
+use crate::utils::fixture;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use wasm_pack::build::WasmPackVersion;
+
+fn version(local: &str, latest: &str) -> WasmPackVersion {
+    WasmPackVersion {
+        local: local.to_string(),
+        latest: latest.to_string(),
+    }
+}
+
+#[test]
+fn versions_are_compared_as_semver() {
+    assert!(version("0.9.1", "0.13.0").is_outdated());
+    assert!(version("0.13.0-alpha.1", "0.13.0").is_outdated());
+    assert!(!version("0.13.0", "0.13.0").is_outdated());
+    assert!(!version("0.14.0", "0.13.0").is_outdated());
+    assert!(!version("0.13.0", "").is_outdated());
+}
+
+#[test]
+fn check_update_reports_the_latest_version() {
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file(
+            "crates-io.json",
+            r#"{ "crate": { "max_version": "99.0.0" } }"#,
+        )
+        .file(
+            "wasm-pack.toml",
+            format!(
+                "update-check-url = \"file://{}\"\n",
+                fixture.path.join("crates-io.json").display()
+            ),
+        );
+
+    fixture
+        .wasm_pack()
+        .env("XDG_STATE_HOME", fixture.path.join("state"))
+        .arg("self")
+        .arg("check-update")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "There's a newer version of wasm-pack available, the new version is: 99.0.0",
+        ));
+    let stamps = std::fs::read_to_string(fixture.path.join("state/wasm-pack/stamps.json")).unwrap();
+    assert!(stamps.contains("\"wasm_pack_latest_version\": \"99.0.0\""));
+
+    // The stamp that was just saved doesn't stop the command from looking the
+    // version up again.
+    fixture.file(
+        "crates-io.json",
+        r#"{ "crate": { "max_version": "0.1.0" } }"#,
+    );
+    fixture
+        .wasm_pack()
+        .env("XDG_STATE_HOME", fixture.path.join("state"))
+        .arg("--message-format")
+        .arg("json")
+        .arg("self")
+        .arg("check-update")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            r#"{{"event":"update-check","local":"{}","latest":"0.1.0","outdated":false}}"#,
+            env!("CARGO_PKG_VERSION")
+        )));
+}
+
+#[test]
+fn check_update_fails_when_the_version_cant_be_looked_up() {
+    let fixture = fixture::Fixture::new();
+    // Disabling the background check doesn't disable the command.
+    fixture.file("wasm-pack.toml", "update-check = false\n");
+    fixture
+        .wasm_pack()
+        .env("XDG_STATE_HOME", fixture.path.join("state"))
+        .env(
+            "WASM_PACK_UPDATE_CHECK_URL",
+            format!("file://{}", fixture.path.join("missing.json").display()),
+        )
+        .arg("self")
+        .arg("check-update")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "failed to look up the latest wasm-pack",
+        ));
+}
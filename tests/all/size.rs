@@ -0,0 +1,244 @@
+use crate::utils::fixture;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::path::PathBuf;
+use wasm_pack::command::build::BuildProfile;
+use wasm_pack::manifest::CrateData;
+use wasm_pack::size::{self, Baseline, ByteSize, MaxGrowth, SizeReport};
+
+/// A module with a type section, an empty code section and a custom `name`
+/// section.
+fn module() -> Vec<u8> {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    // type section: one `() -> ()` function type
+    wasm.extend_from_slice(&[1, 4, 1, 0x60, 0, 0]);
+    // code section: no functions
+    wasm.extend_from_slice(&[10, 1, 0]);
+    // custom section called "name" with two bytes of payload
+    wasm.extend_from_slice(&[0, 7, 4, b'n', b'a', b'm', b'e', 1, 2]);
+    wasm
+}
+
+#[test]
+fn it_splits_a_module_into_sections() {
+    let sections = size::sections(&module()).unwrap();
+    let names: Vec<(&str, u64)> = sections
+        .iter()
+        .map(|section| (section.name.as_str(), section.size))
+        .collect();
+    assert_eq!(
+        names,
+        vec![("type", 6), ("code", 3), ("custom \"name\"", 9)]
+    );
+    assert!(size::sections(b"not wasm").is_err());
+    assert!(size::sections(&module()[..12]).is_err());
+}
+
+#[test]
+fn it_measures_wasm_and_js_files() {
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file("pkg/index_bg.wasm", module())
+        .file("pkg/index.js", "export function greet() {}\n".repeat(100))
+        .file("pkg/package.json", "{}");
+    let report = SizeReport::new(&fixture.path.join("pkg")).unwrap();
+
+    let paths: Vec<&PathBuf> = report.files.iter().map(|file| &file.path).collect();
+    assert_eq!(
+        paths,
+        vec![&PathBuf::from("index.js"), &PathBuf::from("index_bg.wasm")]
+    );
+    let js = &report.files[0];
+    assert_eq!(js.raw, 2700);
+    assert!(js.gzip < js.raw);
+    assert!(js.brotli < js.raw);
+    assert_eq!(report.total(true).0, module().len() as u64);
+    assert_eq!(report.modules.len(), 1);
+    assert_eq!(report.modules[0].sections.len(), 3);
+    assert!(report.table().contains("index_bg.wasm"));
+
+    let quick = SizeReport::quick(&fixture.path.join("pkg")).unwrap();
+    assert_eq!(quick.files[0].raw, js.raw);
+    assert_eq!(quick.files[0].gzip, js.gzip);
+    assert!(quick.files[0].brotli >= js.brotli);
+
+    let budget = toml::from_str(
+        r#"
+        wasm = 1000
+        js = "2 KiB"
+        js-gzip = "1MB"
+        "#,
+    )
+    .unwrap();
+    let exceeded = report.exceeded(&budget);
+    assert_eq!(exceeded.len(), 1);
+    assert!(exceeded[0].starts_with("js is 2.6 KiB"), "{}", exceeded[0]);
+}
+
+#[test]
+fn it_parses_sizes_with_units() {
+    assert_eq!("1500".parse::<ByteSize>().unwrap(), ByteSize(1500));
+    assert_eq!("150 KiB".parse::<ByteSize>().unwrap(), ByteSize(153_600));
+    assert_eq!("1.5MB".parse::<ByteSize>().unwrap(), ByteSize(1_500_000));
+    assert!("12 parsecs".parse::<ByteSize>().is_err());
+}
+
+#[test]
+fn it_reads_the_size_budget_of_a_profile() {
+    let fixture = fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack.profile.release.size-budget]
+            wasm-gzip = "150 KiB"
+            js = 20000
+            "#,
+    );
+    fixture.cargo_check();
+    let crate_data = CrateData::new(&fixture.path, None).unwrap();
+
+    let budget = crate_data
+        .configured_profile(BuildProfile::Release)
+        .size_budget()
+        .unwrap();
+    assert_eq!(budget.wasm_gzip, Some(ByteSize(153_600)));
+    assert_eq!(budget.js, Some(ByteSize(20000)));
+    assert_eq!(budget.wasm, None);
+    assert!(crate_data
+        .configured_profile(BuildProfile::Dev)
+        .size_budget()
+        .is_none());
+}
+
+/// A module importing one function and defining two, `small` and `big`, where
+/// `big` has `nops` extra instructions, with a `name` section naming them.
+fn named_module(nops: usize) -> Vec<u8> {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    wasm.extend_from_slice(&[1, 4, 1, 0x60, 0, 0]);
+    wasm.extend_from_slice(&[2, 7, 1, 1, b'm', 1, b'f', 0, 0]);
+    wasm.extend_from_slice(&[3, 3, 2, 0, 0]);
+
+    let mut big = vec![0];
+    big.resize(1 + nops, 0x01);
+    big.push(0x0b);
+    let mut code = vec![2, 2, 0, 0x0b, big.len() as u8];
+    code.extend(big);
+    wasm.extend_from_slice(&[10, code.len() as u8]);
+    wasm.extend(code);
+
+    let names = [
+        3, 0, 3, b'i', b'm', b'p', 1, 5, b's', b'm', b'a', b'l', b'l', 2, 3, b'b', b'i', b'g',
+    ];
+    let mut name_section = vec![4, b'n', b'a', b'm', b'e', 1, names.len() as u8];
+    name_section.extend_from_slice(&names);
+    wasm.extend_from_slice(&[0, name_section.len() as u8]);
+    wasm.extend(name_section);
+    wasm
+}
+
+#[test]
+fn it_measures_functions_by_name() {
+    let sizes = size::function_sizes(&named_module(2)).unwrap().unwrap();
+    let sizes: Vec<(&str, u64)> = sizes
+        .iter()
+        .map(|(name, size)| (name.as_str(), *size))
+        .collect();
+    // Imported functions have no body, and each body's size includes its
+    // length prefix.
+    assert_eq!(sizes, vec![("big", 5), ("small", 3)]);
+    // A module without a `name` section.
+    assert_eq!(size::function_sizes(&module()[..17]).unwrap(), None);
+}
+
+#[test]
+fn it_compares_against_a_baseline() {
+    let fixture = fixture::Fixture::new();
+    fixture.file("pkg/index_bg.wasm", named_module(2));
+    let out_dir = fixture.path.join("pkg");
+    let baseline_path = fixture.path.join("sizes.json");
+    Baseline::new(&out_dir)
+        .unwrap()
+        .save(&baseline_path)
+        .unwrap();
+    let baseline = Baseline::load(&baseline_path).unwrap();
+
+    fixture.file("pkg/index_bg.wasm", named_module(12));
+    let comparison = baseline.compare(&Baseline::new(&out_dir).unwrap());
+    assert_eq!(comparison.delta(), 10);
+    let growers: Vec<(&str, i64)> = comparison
+        .growers(10)
+        .map(|change| (change.name.as_str(), change.delta()))
+        .collect();
+    assert_eq!(growers, vec![("big", 10)]);
+    assert_eq!(comparison.shrinkers(10).count(), 0);
+    assert!(comparison
+        .sections
+        .iter()
+        .any(|change| change.name == "code"));
+    assert!(comparison.table(10).contains("+10 B  big"));
+
+    assert!("9"
+        .parse::<MaxGrowth>()
+        .unwrap()
+        .is_exceeded_by(&comparison));
+    assert!(!"10"
+        .parse::<MaxGrowth>()
+        .unwrap()
+        .is_exceeded_by(&comparison));
+    assert!("1%"
+        .parse::<MaxGrowth>()
+        .unwrap()
+        .is_exceeded_by(&comparison));
+    assert!(!"50%"
+        .parse::<MaxGrowth>()
+        .unwrap()
+        .is_exceeded_by(&comparison));
+}
+
+#[test]
+fn size_command_fails_when_growing_past_the_threshold() {
+    let fixture = fixture::js_hello_world();
+    fixture.file("pkg/js_hello_world_bg.wasm", named_module(2));
+    fixture
+        .wasm_pack()
+        .arg("size")
+        .arg("--save-baseline")
+        .arg("sizes.json")
+        .assert()
+        .success();
+    assert!(fixture.path.join("sizes.json").exists());
+
+    fixture.file("pkg/js_hello_world_bg.wasm", named_module(40));
+    fixture
+        .wasm_pack()
+        .arg("size")
+        .arg("--compare")
+        .arg("sizes.json")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("largest growers"));
+    fixture
+        .wasm_pack()
+        .arg("size")
+        .arg("--compare")
+        .arg("sizes.json")
+        .arg("--max-growth")
+        .arg("10%")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("more than the allowed 10%"));
+}
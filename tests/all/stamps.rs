@@ -1,22 +1,35 @@
-use std::{fs, panic};
+use std::ffi::OsString;
+use std::path::Path;
+use std::{env, fs, panic};
 use wasm_pack::stamps;
 
 fn run_test<T>(test: T) -> ()
 where
     T: FnOnce() -> () + panic::UnwindSafe,
 {
-    before();
+    let previous_state_home = before();
     let result = panic::catch_unwind(|| test());
-    after();
+    after(previous_state_home);
     assert!(result.is_ok())
 }
 
-fn before() {
-    remove_stamps_file()
+/// Returns the `XDG_STATE_HOME` to restore after the test.
+fn before() -> Option<OsString> {
+    // Keep the stamps of the tests away from the user's cache.
+    let previous = env::var_os("XDG_STATE_HOME");
+    let state_home = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/test_state");
+    env::set_var("XDG_STATE_HOME", state_home);
+    remove_stamps_file();
+    previous
 }
 
-fn after() {
-    remove_stamps_file()
+fn after(previous_state_home: Option<OsString>) {
+    remove_stamps_file();
+    // The other tests in this process must not use the tests' stamps.
+    match previous_state_home {
+        Some(state_home) => env::set_var("XDG_STATE_HOME", state_home),
+        None => env::remove_var("XDG_STATE_HOME"),
+    }
 }
 
 fn remove_stamps_file() {
@@ -69,3 +82,67 @@ fn update_stamp() {
         assert_eq!(stamp_value, "John");
     })
 }
+
+#[test]
+#[serial]
+fn concurrent_updates_keep_every_stamp() {
+    run_test(|| {
+        // ACT
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    stamps::save_stamp_value(format!("Foo{}", i), i.to_string()).unwrap()
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // ASSERT
+        let json = stamps::read_stamps_file_to_json().unwrap();
+        for i in 0..8 {
+            let stamp_value = stamps::get_stamp_value(format!("Foo{}", i), &json).unwrap();
+            assert_eq!(stamp_value, i.to_string());
+        }
+    })
+}
+
+#[test]
+#[serial]
+fn stamps_next_to_the_executable_are_migrated() {
+    run_test(|| {
+        // ARRANGE
+        let exe = env::current_exe().unwrap();
+        fs::write(
+            exe.with_extension("stamps"),
+            r#"{ "chromedriver_version": "126.0.6478.126" }"#,
+        )
+        .unwrap();
+        fs::write(
+            exe.with_extension("stamp"),
+            "created 2024-05-02T14:31:00.123+02:00\nversion 0.13.0",
+        )
+        .unwrap();
+
+        // ACT
+        let json = stamps::read_stamps_file_to_json().unwrap();
+
+        // ASSERT
+        let stamp_value = |key| stamps::get_stamp_value(key, &json).unwrap();
+        assert_eq!(stamp_value("chromedriver_version"), "126.0.6478.126");
+        assert_eq!(
+            stamp_value(stamps::WASM_PACK_LAST_CHECKED_STAMP),
+            "2024-05-02T14:31:00.123+02:00"
+        );
+        assert_eq!(
+            stamp_value(stamps::WASM_PACK_LATEST_VERSION_STAMP),
+            "0.13.0"
+        );
+        assert!(!exe.with_extension("stamps").exists());
+        assert!(!exe.with_extension("stamp").exists());
+        assert!(stamps::get_stamps_file_path()
+            .unwrap()
+            .ends_with("test_state/wasm-pack/stamps.json"));
+    })
+}
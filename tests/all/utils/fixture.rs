@@ -294,7 +294,7 @@ impl Fixture {
         let cache = self.cache();
 
         INSTALL_WASM_OPT.call_once(|| {
-            wasm_pack::wasm_opt::find_wasm_opt(&cache, true).unwrap();
+            wasm_pack::wasm_opt::find_wasm_opt(&cache, None, true).unwrap();
         });
     }
 
@@ -809,3 +809,64 @@ pub fn non_standard_license(license_file: &str) -> Fixture {
         .hello_world_src_lib();
     fixture
 }
+
+/// A virtual workspace with two `cdylib` members, `first` and `second`, and
+/// an `rlib`-only member, `helper`.
+pub fn workspace_with_cdylib_members() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.file(
+        "Cargo.toml",
+        r#"
+            [workspace]
+            members = ["first", "second", "helper"]
+        "#,
+    );
+    for name in &["first", "second"] {
+        fixture.file(
+            Path::new(name).join("Cargo.toml"),
+            format!(
+                r#"
+                    [package]
+                    authors = ["The wasm-pack developers"]
+                    description = "so awesome rust+wasm package"
+                    license = "WTFPL"
+                    name = "{}"
+                    repository = "https://github.com/rustwasm/wasm-pack.git"
+                    version = "0.1.0"
+
+                    [lib]
+                    crate-type = ["cdylib"]
+
+                    [dependencies]
+                    wasm-bindgen = "=0.2.74"
+                "#,
+                name
+            ),
+        );
+        fixture.file(
+            Path::new(name).join("src").join("lib.rs"),
+            r#"
+                extern crate wasm_bindgen;
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen]
+                pub fn hello() -> u32 { 42 }
+            "#,
+        );
+    }
+    fixture
+        .file(
+            Path::new("helper").join("Cargo.toml"),
+            r#"
+                [package]
+                authors = ["The wasm-pack developers"]
+                name = "helper"
+                version = "0.1.0"
+            "#,
+        )
+        .file(
+            Path::new("helper").join("src").join("lib.rs"),
+            "pub fn helper() {}",
+        );
+    fixture
+}
@@ -28,6 +28,8 @@ pub struct NpmPackage {
     pub homepage: Option<String>,
     pub keywords: Option<Vec<String>>,
     pub dependencies: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub exports: serde_json::Value,
 }
 
 fn default_none() -> String {
@@ -50,6 +52,11 @@ pub fn read_package_json(path: &Path, out_dir: &Path) -> Result<NpmPackage> {
     Ok(serde_json::from_str(&pkg_contents)?)
 }
 
+pub fn read_package_json_value(out_dir: &Path) -> Result<serde_json::Value> {
+    let contents = std::fs::read_to_string(out_dir.join("package.json"))?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
 pub fn create_wbg_package_json(out_dir: &Path, contents: &str) -> Result<()> {
     let manifest_path = out_dir.join("package.json");
     Ok(std::fs::write(manifest_path, contents)?)
@@ -187,3 +187,25 @@ fn misconfigured() {
         .stderr(predicates::str::contains("failed to parse manifest"))
         .failure();
 }
+
+#[test]
+#[cfg(unix)]
+fn reads_the_version_of_wasm_opt() {
+    use std::os::unix::fs::PermissionsExt;
+    use wasm_pack::install::{self, Tool};
+
+    let fixture = utils::fixture::Fixture::new();
+    fixture.file(
+        "bin/wasm-opt",
+        "#!/bin/sh\necho 'wasm-opt version 119 (version_119)'\n",
+    );
+    let path = fixture.path.join("bin/wasm-opt");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    assert_eq!(
+        install::get_cli_version(&Tool::WasmOpt, &path).unwrap(),
+        "version_119"
+    );
+    assert!(install::check_version(&Tool::WasmOpt, &path, "version_119").unwrap());
+    assert!(!install::check_version(&Tool::WasmOpt, &path, "version_117").unwrap());
+}
@@ -0,0 +1,150 @@
+use crate::utils::fixture;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::time::Duration;
+use wasm_pack::command::build::Target;
+use wasm_pack::fingerprint::{Fingerprints, Record};
+use wasm_pack::watch::{clean_staging_dir, sync_dir, Watcher};
+
+#[test]
+fn sync_dir_only_writes_changed_files() {
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file("staging/a.js", "a")
+        .file("staging/b.wasm", "b")
+        .file("staging/snippets/c.js", "c");
+    let staging = fixture.path.join("staging");
+    let out_dir = fixture.path.join("pkg");
+
+    let (synced, changed) = sync_dir(&staging, &out_dir, &BTreeSet::new()).unwrap();
+    assert_eq!(changed.len(), 3);
+    assert_eq!(synced.len(), 3);
+    assert_eq!(
+        fs::read_to_string(out_dir.join("snippets/c.js")).unwrap(),
+        "c"
+    );
+
+    fixture.file("staging/a.js", "a2");
+    let (synced, changed) = sync_dir(&staging, &out_dir, &synced).unwrap();
+    assert_eq!(changed, vec![PathBuf::from("a.js")]);
+    assert_eq!(fs::read_to_string(out_dir.join("a.js")).unwrap(), "a2");
+
+    // Files which are no longer generated are removed, but files that were
+    // never synced are left alone.
+    fs::remove_file(staging.join("b.wasm")).unwrap();
+    fixture.file("pkg/user-file.txt", "keep me");
+    let (_, changed) = sync_dir(&staging, &out_dir, &synced).unwrap();
+    assert_eq!(changed, vec![PathBuf::from("b.wasm")]);
+    assert!(!out_dir.join("b.wasm").exists());
+    assert!(out_dir.join("user-file.txt").exists());
+}
+
+#[test]
+fn watcher_snapshot_ignores_target_and_hidden_dirs() {
+    let fixture = fixture::js_hello_world();
+    fixture
+        .file("target/debug/out.rlib", "artifact")
+        .file(".git/HEAD", "ref")
+        .file("pkg/index.js", "generated");
+    let watcher = Watcher::new(
+        vec![fixture.path.clone()],
+        vec![fixture.path.join("target"), fixture.path.join("pkg")],
+    );
+
+    let before = watcher.snapshot();
+    fixture
+        .file("target/debug/out.rlib", "rebuilt artifact")
+        .file(".git/HEAD", "other ref")
+        .file("pkg/index.js", "regenerated");
+    assert_eq!(watcher.snapshot(), before);
+
+    fixture.file("src/other.rs", "pub fn other() {}");
+    assert_ne!(watcher.snapshot(), before);
+}
+
+#[test]
+fn clean_staging_dir_keeps_the_fingerprinted_bindings() {
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file("staging/web/hello.js", "")
+        .file("staging/web/hello_bg.wasm", "")
+        .file("staging/web/snippets/a.js", "")
+        .file("staging/web/package.json", "")
+        .file("staging/old/hello.js", "")
+        .file("staging/package.json", "")
+        .file("staging/README.md", "");
+    let staging = fixture.path.join("staging");
+    let mut fingerprints = Fingerprints::default();
+    fingerprints.insert(
+        Target::Web,
+        Record {
+            fingerprint: "abc".to_string(),
+            npm_dependencies: None,
+            outputs: vec![
+                "web/hello.js".into(),
+                "web/hello_bg.wasm".into(),
+                "web/snippets".into(),
+            ],
+        },
+    );
+    fingerprints.save(&staging).unwrap();
+
+    clean_staging_dir(&staging).unwrap();
+    assert!(staging.join("web/hello.js").exists());
+    assert!(staging.join("web/hello_bg.wasm").exists());
+    assert!(staging.join("web/snippets/a.js").exists());
+    assert!(!staging.join("web/package.json").exists());
+    assert!(!staging.join("old").exists());
+    assert!(!staging.join("package.json").exists());
+    assert!(!staging.join("README.md").exists());
+    assert!(Fingerprints::load(&staging).get(Target::Web).is_some());
+}
+
+#[test]
+fn rebuilds_skip_wasm_bindgen_when_the_wasm_is_unchanged() {
+    let fixture = fixture::js_hello_world();
+    fixture.install_local_wasm_bindgen();
+    let mut child = fixture
+        .wasm_pack()
+        .arg("watch")
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (lines, received) = mpsc::channel();
+    let stderr = BufReader::new(child.stderr.take().unwrap());
+    std::thread::spawn(move || {
+        for line in stderr.lines().map_while(Result::ok) {
+            let _ = lines.send(line);
+        }
+    });
+    // Wait for a rebuild to finish, returning whether wasm-bindgen was skipped.
+    let rebuild = || {
+        let mut skipped = false;
+        loop {
+            let line = received.recv_timeout(Duration::from_secs(300)).unwrap();
+            skipped |= line.contains("skipping wasm-bindgen and wasm-opt");
+            if line.contains("Rebuilt in") {
+                return skipped;
+            }
+        }
+    };
+
+    let first = rebuild();
+    // Touch the sources without changing them, so that cargo compiles the
+    // same wasm again.
+    let lib = fixture.path.join("src/lib.rs");
+    std::thread::sleep(Duration::from_secs(1));
+    fs::write(&lib, fs::read(&lib).unwrap()).unwrap();
+    let second = rebuild();
+    child.kill().unwrap();
+    let _ = child.wait();
+
+    assert!(!first);
+    assert!(second);
+    assert!(fixture.path.join("pkg/js_hello_world_bg.wasm").exists());
+}